@@ -0,0 +1,44 @@
+//! Deserialization from tokio's `AsyncRead`.  Only available with the `tokio`
+//! feature.
+
+use serde::de::DeserializeOwned;
+use tokio::io::{self, AsyncRead};
+use tokio::prelude::Future;
+
+use crate::de::from_bytes;
+use crate::error::Error;
+
+/// Deserializes some EETF from an `AsyncRead`.
+///
+/// This reads the whole of `reader` into memory before decoding it, so the
+/// reader must end after the term, e.g. when the other side closes the
+/// connection.  Use a framing codec to read terms from a long-lived stream.
+pub fn from_async_reader<R, T>(reader: R) -> impl Future<Item = T, Error = Error>
+where
+    R: AsyncRead,
+    T: DeserializeOwned,
+{
+    io::read_to_end(reader, Vec::new())
+        .map_err(|e| Error::from(eetf::DecodeError::from(e)))
+        .and_then(|(_, bytes)| from_bytes(&bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::Cursor;
+
+    use crate::to_bytes;
+
+    #[test]
+    fn test_from_async_reader() {
+        let bytes = to_bytes(&(1u8, "two".to_string())).expect("serialize failed");
+
+        let result: (u8, String) = from_async_reader(Cursor::new(bytes))
+            .wait()
+            .expect("deserialize failed");
+
+        assert_eq!(result, (1, "two".to_string()));
+    }
+}
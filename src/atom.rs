@@ -0,0 +1,191 @@
+//! Support for explicitly serializing strings as atoms.
+
+use std::fmt;
+
+use serde::de::{self, Deserialize, Deserializer, Visitor};
+use serde::ser::{Serialize, Serializer};
+
+// The name of the newtype struct that Atom serializes as.  Our Serializer &
+// Deserializer recognise this and use an atom rather than a binary.  Other
+// formats just see a newtype struct holding a string.
+pub(crate) const TOKEN: &str = "$serde_eetf::Atom";
+
+/// A string that is serialized as an atom rather than a binary.
+///
+/// ```rust
+/// # #[macro_use]
+/// # extern crate serde_derive;
+/// # extern crate serde_eetf;
+/// # fn main() {
+/// use serde_eetf::{to_bytes, Atom};
+///
+/// // Becomes #{node => 'worker@host', name => <<"jobs">>}
+/// #[derive(Serialize)]
+/// struct Queue {
+///     node: Atom,
+///     name: String,
+/// }
+///
+/// let bytes = to_bytes(&Queue {
+///     node: Atom::from("worker@host"),
+///     name: "jobs".to_string(),
+/// })
+/// .unwrap();
+/// # }
+/// ```
+///
+/// The name is used verbatim.  When deserializing, only atoms are accepted.
+///
+/// Beware that atoms are never garbage collected, so this shouldn't be used
+/// for strings that come from user input.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Atom(pub String);
+
+impl From<String> for Atom {
+    fn from(name: String) -> Self {
+        Atom(name)
+    }
+}
+
+impl<'a> From<&'a str> for Atom {
+    fn from(name: &'a str) -> Self {
+        Atom(name.to_string())
+    }
+}
+
+impl From<Atom> for String {
+    fn from(atom: Atom) -> Self {
+        atom.0
+    }
+}
+
+impl fmt::Display for Atom {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str(&self.0)
+    }
+}
+
+impl Serialize for Atom {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_newtype_struct(TOKEN, &self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Atom {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_newtype_struct(TOKEN, AtomVisitor)
+    }
+}
+
+struct AtomVisitor;
+
+impl<'de> Visitor<'de> for AtomVisitor {
+    type Value = Atom;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an atom")
+    }
+
+    // Our deserializer provides the name of the atom.
+    fn visit_str<E>(self, v: &str) -> Result<Atom, E>
+    where
+        E: de::Error,
+    {
+        Ok(Atom::from(v))
+    }
+
+    fn visit_string<E>(self, v: String) -> Result<Atom, E>
+    where
+        E: de::Error,
+    {
+        Ok(Atom(v))
+    }
+
+    // Other deserializers treat this as a newtype struct holding a string.
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Atom, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        String::deserialize(deserializer).map(Atom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io;
+
+    use eetf::{self, Term};
+
+    use crate::{
+        from_bytes, to_bytes, to_bytes_with_config, Error, SerializerConfig, StringFormat,
+    };
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Queue {
+        node: Atom,
+        name: String,
+    }
+
+    #[test]
+    fn test_atom_roundtrip() {
+        let queue = Queue {
+            node: Atom::from("worker@host"),
+            name: "jobs".to_string(),
+        };
+
+        let bytes = to_bytes(&queue).expect("serialize failed");
+        assert_eq!(
+            Term::decode(io::Cursor::new(&bytes)).expect("decode failed"),
+            Term::Map(eetf::Map::from(vec![
+                (
+                    Term::Atom(eetf::Atom::from("node")),
+                    Term::Atom(eetf::Atom::from("worker@host")),
+                ),
+                (
+                    Term::Atom(eetf::Atom::from("name")),
+                    Term::Binary(eetf::Binary::from("jobs".as_bytes())),
+                ),
+            ]))
+        );
+
+        let result: Queue = from_bytes(&bytes).expect("deserialize failed");
+        assert_eq!(result, queue);
+    }
+
+    #[test]
+    fn test_atom_ignores_string_format() {
+        let config = SerializerConfig {
+            string_format: StringFormat::CharList,
+            ..SerializerConfig::default()
+        };
+
+        let bytes = to_bytes_with_config(&Atom::from("ok"), &config).expect("serialize failed");
+
+        assert_eq!(
+            Term::decode(io::Cursor::new(&bytes)).expect("decode failed"),
+            Term::Atom(eetf::Atom::from("ok"))
+        );
+    }
+
+    #[test]
+    fn test_atom_rejects_binaries() {
+        let bytes = to_bytes("ok").expect("serialize failed");
+
+        assert_eq!(
+            from_bytes::<Atom>(&bytes),
+            Err(Error::TypeMismatch {
+                expected: "atom",
+                got: "binary",
+                term: Term::Binary(eetf::Binary::from("ok".as_bytes())).to_string()
+            })
+        );
+    }
+}
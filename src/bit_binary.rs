@@ -0,0 +1,87 @@
+//! Support for Erlang bitstrings.
+
+use eetf::{self, Term};
+use serde::de::{Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
+
+use crate::value;
+
+/// A bitstring whose length isn't a whole number of bytes, e.g. `<<1:3>>`.
+///
+/// Only the top `tail_bits` bits of the last byte are part of the bitstring.
+/// See `EetfValue` for which serializers this works with.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct BitBinary {
+    pub bytes: Vec<u8>,
+    /// The number of bits used in the last byte, from 1 to 8.
+    pub tail_bits: u8,
+}
+
+impl From<eetf::BitBinary> for BitBinary {
+    fn from(binary: eetf::BitBinary) -> Self {
+        BitBinary {
+            bytes: binary.bytes,
+            tail_bits: binary.tail_bits_size,
+        }
+    }
+}
+
+impl From<BitBinary> for eetf::BitBinary {
+    fn from(binary: BitBinary) -> Self {
+        eetf::BitBinary {
+            bytes: binary.bytes,
+            tail_bits_size: binary.tail_bits,
+        }
+    }
+}
+
+impl Serialize for BitBinary {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        value::serialize_term(Term::BitBinary(self.clone().into()), serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for BitBinary {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        value::deserialize_term(deserializer, "a bitstring", |term| match term {
+            Term::BitBinary(binary) => Ok(BitBinary::from(binary)),
+            other => Err(other),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io;
+
+    use crate::{from_bytes, to_bytes};
+
+    #[test]
+    fn test_bit_binary_roundtrip() {
+        // <<255, 5:3>>
+        let binary = BitBinary {
+            bytes: vec![255, 0b1010_0000],
+            tail_bits: 3,
+        };
+
+        let bytes = to_bytes(&binary).expect("serialize failed");
+        assert_eq!(
+            Term::decode(io::Cursor::new(&bytes)).expect("decode failed"),
+            Term::BitBinary(eetf::BitBinary {
+                bytes: vec![255, 0b1010_0000],
+                tail_bits_size: 3,
+            })
+        );
+
+        let result: BitBinary = from_bytes(&bytes).expect("deserialize failed");
+        assert_eq!(result, binary);
+    }
+}
@@ -0,0 +1,561 @@
+//! Options for tweaking how rust values are represented in EETF.
+
+/// How the variant name is encoded in the tag position of the tuples that
+/// newtype, tuple & struct variants serialize to.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum VariantTag {
+    /// Tag tuples with an atom, e.g. `{ok, Value}`.  This is the default.
+    Atom,
+    /// Tag tuples with a binary, e.g. `{<<"ok">>, Value}`, as some Elixir code
+    /// does.
+    Binary,
+}
+
+impl Default for VariantTag {
+    fn default() -> Self {
+        VariantTag::Atom
+    }
+}
+
+/// How rust strings & chars are serialized.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StringFormat {
+    /// Strings become UTF-8 binaries, e.g. `<<"hello">>`, and chars become
+    /// single character binaries.  This is the default.
+    Binary,
+    /// Strings become charlists, i.e. lists of integer code points, and chars
+    /// become a single integer code point, as they would be in Erlang.
+    ///
+    /// Each char is emitted as its unicode scalar value rather than its UTF-8
+    /// bytes, e.g. `"é"` becomes `[233]`, the same as `"é"` in Erlang source.
+    CharList,
+}
+
+impl Default for StringFormat {
+    fn default() -> Self {
+        StringFormat::Binary
+    }
+}
+
+/// How rust chars are serialized.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CharFormat {
+    /// Chars are serialized like a string of one character, following the
+    /// `StringFormat`.  This is the default.
+    String,
+    /// Chars become their integer code point, e.g. `'A'` becomes `65`, as
+    /// `$A` would in Erlang.
+    Integer,
+}
+
+impl Default for CharFormat {
+    fn default() -> Self {
+        CharFormat::String
+    }
+}
+
+/// How booleans are serialized.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BoolFormat {
+    /// The atoms `true` & `false`, as used by Erlang.  This is the default.
+    Atom,
+    /// The integers `1` & `0`, as some C-nodes expect.
+    Integer,
+}
+
+impl Default for BoolFormat {
+    fn default() -> Self {
+        BoolFormat::Atom
+    }
+}
+
+/// How the field names of structs & struct variants are serialized.
+///
+/// Field names are used verbatim, so a field named `userId` becomes `userId`.
+/// This differs from enum variant names, which are converted to snake_case to
+/// fit Erlang's atom conventions, e.g. `TextMessage` becomes `text_message`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum KeyFormat {
+    /// Atom keys, e.g. `#{name => Value}`.  This is the default.
+    Atom,
+    /// Binary keys, e.g. `#{<<"name">> => Value}`, as is common in Elixir
+    /// code that handles JSON-like data.
+    Binary,
+}
+
+impl Default for KeyFormat {
+    fn default() -> Self {
+        KeyFormat::Atom
+    }
+}
+
+/// How the names of enum variants are converted to & from their tags.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum VariantCase {
+    /// Names are converted to snake_case to fit Erlang's atom conventions,
+    /// e.g. `TextMessage` becomes `text_message`.  This is the default.
+    SnakeCase,
+    /// Names are used verbatim, e.g. `TextMessage` stays `TextMessage`.  When
+    /// deserializing, a tag has to match the name of a variant exactly.
+    ///
+    /// Use this when naming is already controlled with serde attributes like
+    /// `#[serde(rename_all = "...")]`, which `SnakeCase` would otherwise
+    /// override.
+    AsIs,
+}
+
+impl VariantCase {
+    pub(crate) const DEFAULT: Self = VariantCase::SnakeCase;
+}
+
+impl Default for VariantCase {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// How the contents of tuple & struct variants are represented.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum VariantFormat {
+    /// The contents are nested in a tuple or map after the tag, e.g.
+    /// `Move(1, 2)` becomes `{move, {1, 2}}` and `Move { x: 1, y: 2 }`
+    /// becomes `{move, #{x => 1, y => 2}}`.  This is the default.
+    Map,
+    /// The contents follow the tag in one flat tuple, like an Erlang record,
+    /// e.g. both `Move(1, 2)` and `Move { x: 1, y: 2 }` become `{move, 1, 2}`.
+    /// The fields of struct variants are in the order they're declared, &
+    /// like `StructFormat::Record` none of them can be skipped.
+    FlatTuple,
+}
+
+impl VariantFormat {
+    pub(crate) const DEFAULT: Self = VariantFormat::Map;
+}
+
+impl Default for VariantFormat {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// How the string keys of maps, e.g. a `HashMap<String, T>`, are serialized.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MapKeyFormat {
+    /// Keys are serialized like any other string.  This is the default.
+    Binary,
+    /// Keys become atoms.
+    ///
+    /// Beware that atoms are never garbage collected, so this shouldn't be
+    /// used for keys that come from user input.
+    Atom,
+    /// Keys that look like conventional atoms, i.e. match `[a-z][a-z0-9_]*`,
+    /// become atoms.  Any others are serialized like any other string.
+    SafeAtom,
+}
+
+impl Default for MapKeyFormat {
+    fn default() -> Self {
+        MapKeyFormat::Binary
+    }
+}
+
+/// How tuple structs are serialized.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TupleStructFormat {
+    /// A bare tuple, e.g. `Point(1, 2)` becomes `{1, 2}`.  This is the
+    /// default.
+    Tuple,
+    /// A tuple tagged with the snake_cased name of the struct, like an Erlang
+    /// record, e.g. `Point(1, 2)` becomes `{point, 1, 2}`.
+    Record,
+}
+
+impl Default for TupleStructFormat {
+    fn default() -> Self {
+        TupleStructFormat::Tuple
+    }
+}
+
+/// How structs are serialized.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StructFormat {
+    /// A map keyed by field names, e.g. `User { name, age }` becomes
+    /// `#{name => Name, age => Age}`.  This is the default.
+    Map,
+    /// A tuple of the snake_cased name of the struct & the field values in
+    /// order, like an Erlang record, e.g. `{user, Name, Age}`.
+    ///
+    /// Records are read back by position, so every field has to be present:
+    /// skipping a field with `#[serde(skip_serializing_if = "...")]` is an
+    /// error.
+    Record,
+}
+
+impl Default for StructFormat {
+    fn default() -> Self {
+        StructFormat::Map
+    }
+}
+
+/// How maps with no entries, e.g. an empty `HashMap`, are serialized.
+///
+/// This doesn't apply to structs, which are serialized as `StructFormat`
+/// says even if they have no fields.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EmptyMapFormat {
+    /// An empty map, i.e. `#{}`.  This is the default.
+    Map,
+    /// `SerializerConfig::none_atom`, which is `nil` by default.  Beware that
+    /// an `Option` holding an empty map will then be deserialized as `None`.
+    ///
+    /// These can only be deserialized as maps if
+    /// `DeserializerConfig::accept_nil_maps` is set.
+    Nil,
+    /// An empty list, i.e. `[]`.
+    List,
+}
+
+impl Default for EmptyMapFormat {
+    fn default() -> Self {
+        EmptyMapFormat::Map
+    }
+}
+
+/// How floats are serialized.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FloatFormat {
+    /// EETF floats.  This is the default.
+    Float,
+    /// A binary holding the shortest decimal string that parses back to the
+    /// same float, e.g. `<<"0.1">>` or `<<"1.0e308">>`.  This lets the exact
+    /// value be reproduced by code with its own decimal parsing.  There's
+    /// always a decimal point, as `binary_to_float/1` requires one.
+    String,
+}
+
+impl Default for FloatFormat {
+    fn default() -> Self {
+        FloatFormat::Float
+    }
+}
+
+/// How NaN & infinite floats, which EETF can't represent, are serialized.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NanHandling {
+    /// Serialization fails.  This is the default.
+    Error,
+    /// The atoms `nan`, `infinity` & `neg_infinity`.
+    Atom,
+}
+
+impl Default for NanHandling {
+    fn default() -> Self {
+        NanHandling::Error
+    }
+}
+
+/// How newtype structs, e.g. `struct UserId(u64)`, are represented.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NewtypeStructFormat {
+    /// Just the value the struct wraps, e.g. `UserId(1)` becomes `1`.  This
+    /// is the default.
+    Transparent,
+    /// A tuple of the snake_cased name of the struct & the value it wraps,
+    /// e.g. `UserId(1)` becomes `{user_id, 1}`.
+    Tagged,
+}
+
+impl NewtypeStructFormat {
+    pub(crate) const DEFAULT: Self = NewtypeStructFormat::Transparent;
+}
+
+impl Default for NewtypeStructFormat {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// How unit values, e.g. `()` or a unit struct, are serialized.
+///
+/// Units can be deserialized from any of these.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UnitFormat {
+    /// The same atom as `None`, `nil` by default.  This is the default.
+    Nil,
+    /// An empty tuple, i.e. `{}`, which can't be confused with `None`.
+    EmptyTuple,
+    /// An empty list, i.e. `[]`.
+    EmptyList,
+}
+
+impl Default for UnitFormat {
+    fn default() -> Self {
+        UnitFormat::Nil
+    }
+}
+
+/// How atoms are encoded.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AtomEncoding {
+    /// Atoms that aren't plain ASCII are encoded as UTF-8.  This is the
+    /// default, and is understood by Erlang/OTP 20 & later.
+    Utf8,
+    /// Atoms are encoded as Latin-1, for older nodes that don't accept UTF-8
+    /// atoms.  Serialization fails if an atom has characters outside of
+    /// Latin-1.  This covers the node names in pids, ports & references, but
+    /// not the module & function names in funs.
+    Latin1,
+}
+
+impl Default for AtomEncoding {
+    fn default() -> Self {
+        AtomEncoding::Utf8
+    }
+}
+
+/// Configuration for serialization.
+///
+/// The `Default` impl matches the behaviour of `to_bytes` & `to_writer`.
+#[derive(Clone, Debug)]
+pub struct SerializerConfig {
+    /// How to encode variant names in tagged tuples.
+    ///
+    /// Unit variants are always serialized as a bare atom.
+    pub variant_tag: VariantTag,
+
+    /// How to encode strings & chars.
+    pub string_format: StringFormat,
+
+    /// How to encode chars.
+    ///
+    /// Chars can be deserialized from either format.
+    pub char_format: CharFormat,
+
+    /// How to encode booleans.
+    pub bool_format: BoolFormat,
+
+    /// How to encode the field names of structs & struct variants.
+    pub key_format: KeyFormat,
+
+    /// How to encode the string keys of maps.
+    pub map_key_format: MapKeyFormat,
+
+    /// How to encode tuple structs.
+    ///
+    /// Tuple structs can be deserialized from either format.
+    pub tuple_struct_format: TupleStructFormat,
+
+    /// How to encode structs.
+    ///
+    /// Structs can be deserialized from either format.
+    pub struct_format: StructFormat,
+
+    /// How to encode newtype structs.
+    pub newtype_struct_format: NewtypeStructFormat,
+
+    /// How to encode floats.
+    ///
+    /// Floats can be deserialized from either format.
+    pub float_format: FloatFormat,
+
+    /// How to encode NaN & infinite floats.
+    ///
+    /// The atoms are always accepted when deserializing a float.
+    pub nan_handling: NanHandling,
+
+    /// How to encode maps with no entries.
+    ///
+    /// Maps can be deserialized from any of these formats.
+    pub empty_map_format: EmptyMapFormat,
+
+    /// Whether to sort the entries of maps by their keys, in Erlang's term
+    /// order, so that e.g. a `HashMap` always encodes to the same bytes.
+    ///
+    /// Struct fields are always in the order they're declared.
+    pub sort_map_keys: bool,
+
+    /// How to encode the contents of tuple & struct variants.
+    pub variant_format: VariantFormat,
+
+    /// How to convert the names of enum variants into tags.
+    pub variant_case: VariantCase,
+
+    /// Whether to compress the encoded term with zlib, like
+    /// `term_to_binary(Term, [compressed])`.
+    ///
+    /// Compressed terms are decompressed automatically when deserializing.
+    pub compress: bool,
+
+    /// The atom to encode `None` as, & unit with `UnitFormat::Nil`.  Defaults
+    /// to `nil`, as used by Elixir, but Erlang code usually expects
+    /// `undefined`.
+    pub none_atom: String,
+
+    /// How to encode every atom, including struct keys, variant names &
+    /// booleans.
+    pub atom_encoding: AtomEncoding,
+
+    /// How to encode unit values & unit structs.
+    pub unit_format: UnitFormat,
+}
+
+impl Default for SerializerConfig {
+    fn default() -> Self {
+        SerializerConfig {
+            variant_tag: VariantTag::default(),
+            string_format: StringFormat::default(),
+            char_format: CharFormat::default(),
+            bool_format: BoolFormat::default(),
+            key_format: KeyFormat::default(),
+            map_key_format: MapKeyFormat::default(),
+            tuple_struct_format: TupleStructFormat::default(),
+            struct_format: StructFormat::default(),
+            newtype_struct_format: NewtypeStructFormat::default(),
+            float_format: FloatFormat::default(),
+            nan_handling: NanHandling::default(),
+            empty_map_format: EmptyMapFormat::default(),
+            sort_map_keys: false,
+            variant_format: VariantFormat::default(),
+            variant_case: VariantCase::default(),
+            compress: false,
+            none_atom: "nil".to_string(),
+            atom_encoding: AtomEncoding::default(),
+            unit_format: UnitFormat::default(),
+        }
+    }
+}
+
+/// How binaries are interpreted by types that accept any term, e.g. a
+/// generic `Value` enum.
+///
+/// Types that ask for a string or bytes get what they ask for either way.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BinaryInterpretation {
+    /// Binaries are bytes.  This is the default.
+    Bytes,
+    /// Binaries holding valid UTF-8 are strings, & any others are bytes.
+    StringIfUtf8,
+}
+
+impl BinaryInterpretation {
+    pub(crate) const DEFAULT: Self = BinaryInterpretation::Bytes;
+}
+
+impl Default for BinaryInterpretation {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// How floats are handled when an integer is expected.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FloatToInteger {
+    /// Floats are rejected.  This is the default.
+    Reject,
+    /// Floats with no fractional part, e.g. `1.0`, are accepted.  Others are
+    /// still rejected.
+    AcceptWhole,
+}
+
+impl FloatToInteger {
+    pub(crate) const DEFAULT: Self = FloatToInteger::Reject;
+}
+
+impl Default for FloatToInteger {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Configuration for deserialization.
+///
+/// The `Default` impl matches the behaviour of `from_bytes` & `from_reader`.
+#[derive(Clone, Debug)]
+pub struct DeserializerConfig {
+    /// How to handle a float where an integer is expected.
+    pub float_to_integer: FloatToInteger,
+
+    /// The name of a variant to deserialize `{Tag, Value}` tuples into when
+    /// an enum has no variant matching `Tag`.
+    ///
+    /// This only applies to enums that have a variant with this name.  That
+    /// variant is given the whole tuple, so should usually be a newtype
+    /// variant holding an `EetfValue`, e.g. `Unknown(EetfValue)`.
+    pub catch_all_variant: Option<String>,
+
+    /// How newtype structs are expected to be represented.
+    ///
+    /// Unlike tuple structs this has to be chosen up front, as a tagged
+    /// newtype can't be told apart from a transparent one wrapping a tuple.
+    pub newtype_struct_format: NewtypeStructFormat,
+
+    /// How the contents of tuple & struct variants are expected to be
+    /// represented.
+    ///
+    /// This has to be chosen up front, as a flat tuple variant with one
+    /// element can't be told apart from a newtype variant.
+    pub variant_format: VariantFormat,
+
+    /// How to convert tags into the names of enum variants.
+    pub variant_case: VariantCase,
+
+    /// How deeply terms may be nested before deserialization fails with
+    /// `Error::DepthLimitExceeded`.  Defaults to 128.
+    ///
+    /// This bounds how deeply the deserializer recurses.  It's checked once
+    /// the term has been decoded, so it doesn't limit the recursion of
+    /// decoding itself.
+    pub max_depth: usize,
+
+    /// The most atoms, counting repeats, that a term may contain before
+    /// deserialization fails with `Error::TooManyAtoms`.  Unlimited by
+    /// default.
+    ///
+    /// This is checked before the term is deserialized, so hostile input can
+    /// be rejected before any work is done on it.  Note that the atoms have
+    /// already been decoded by then.
+    pub max_atoms: Option<usize>,
+
+    /// An atom to deserialize as `None`, for use with
+    /// `SerializerConfig::none_atom`.
+    ///
+    /// `nil` & `undefined` are always deserialized as `None`.
+    pub none_atom: Option<String>,
+
+    /// Whether proplists, i.e. lists of `{Key, Value}` tuples keyed by atoms
+    /// or binaries, can be deserialized as structs & maps.  Off by default.
+    pub accept_proplists: bool,
+
+    /// Whether the atoms deserialized as `None` can be deserialized as empty
+    /// maps too, for use with `EmptyMapFormat::Nil`.  Off by default, so that
+    /// a map that was left as `nil` fails with `Error::UnexpectedNil` rather
+    /// than being empty.
+    pub accept_nil_maps: bool,
+
+    /// How binaries are interpreted when the type being deserialized accepts
+    /// any kind of term.
+    pub binary_as: BinaryInterpretation,
+}
+
+impl Default for DeserializerConfig {
+    fn default() -> Self {
+        DEFAULT_DESERIALIZER_CONFIG.clone()
+    }
+}
+
+// Used by deserializers that weren't given a config, and as the Default.
+// The enums' defaults are associated consts, as a static can't call their
+// Default impls.
+pub(crate) static DEFAULT_DESERIALIZER_CONFIG: DeserializerConfig = DeserializerConfig {
+    float_to_integer: FloatToInteger::DEFAULT,
+    catch_all_variant: None,
+    newtype_struct_format: NewtypeStructFormat::DEFAULT,
+    variant_format: VariantFormat::DEFAULT,
+    variant_case: VariantCase::DEFAULT,
+    max_depth: 128,
+    max_atoms: None,
+    none_atom: None,
+    accept_proplists: false,
+    accept_nil_maps: false,
+    binary_as: BinaryInterpretation::DEFAULT,
+};
@@ -1,49 +1,176 @@
 use eetf::Term;
+use std::cell::RefCell;
+use std::convert::TryFrom;
+use std::fmt::{self, Write};
 use std::io::{self, Read};
-use std::iter;
+use std::marker::PhantomData;
+use std::slice;
 use std::str;
 
-use heck::CamelCase;
+use heck::{CamelCase, SnakeCase};
 
 use num_traits::cast::{FromPrimitive, ToPrimitive};
+use num_traits::{Bounded, Signed};
 
 use serde::de::{
     self, DeserializeOwned, DeserializeSeed, EnumAccess, IntoDeserializer, MapAccess, SeqAccess,
     VariantAccess, Visitor,
 };
 
+use crate::atom;
+use crate::config::{
+    BinaryInterpretation, DeserializerConfig, FloatToInteger, NewtypeStructFormat, VariantCase,
+    VariantFormat, DEFAULT_DESERIALIZER_CONFIG,
+};
 use crate::error::{Error, Result};
-
-use self::private::*;
+use crate::ser::{result_tag, VERSION};
+use crate::value;
 
 /// Deserializes an `eetf::Term`
 ///
 /// Generally you should use the from_bytes or from_reader functions instead.
+///
+/// Those functions can only produce types that own all their data, as the
+/// decoder copies everything out of the input into an `eetf::Term`.  To
+/// deserialize a type that borrows `&str` or `&[u8]` fields, decode the term
+/// yourself and deserialize from a `Deserializer` that borrows it.  The
+/// result can then live no longer than the term:
+///
+/// ```rust
+/// # #[macro_use]
+/// # extern crate serde_derive;
+/// # extern crate eetf;
+/// # extern crate serde;
+/// # extern crate serde_eetf;
+/// # fn main() {
+/// use serde::Deserialize;
+/// use serde_eetf::{to_bytes, Deserializer};
+///
+/// #[derive(Deserialize)]
+/// struct Message<'a> {
+///     text: &'a str,
+/// }
+///
+/// # #[derive(Serialize)]
+/// # struct OwnedMessage { text: String }
+/// # let bytes = to_bytes(&OwnedMessage { text: "hello".to_string() }).unwrap();
+/// let term = eetf::Term::decode(bytes.as_slice()).unwrap();
+/// let message = Message::deserialize(Deserializer::from_term(&term)).unwrap();
+/// assert_eq!(message.text, "hello");
+/// # }
+/// ```
+#[derive(Clone, Copy)]
 pub struct Deserializer<'a> {
     term: &'a Term,
+    config: &'a DeserializerConfig,
+    warnings: Option<&'a RefCell<Vec<Warning>>>,
+    path: Option<&'a RefCell<Vec<PathSegment>>>,
+    // How many terms deep into the input we are.
+    depth: usize,
 }
 
 impl<'a> Deserializer<'a> {
+    /// Creates a Deserializer that borrows `term`.
+    ///
+    /// Binaries & strings are handed to the type being deserialized without
+    /// copying them, through `visit_borrowed_str` & `visit_borrowed_bytes`,
+    /// so fields of type `&'a str` & `&'a [u8]` borrow from `term`.
     pub fn from_term(term: &'a Term) -> Self {
-        Deserializer { term }
+        Self::from_term_with_config(term, &DEFAULT_DESERIALIZER_CONFIG)
     }
-}
 
-trait IntoEetfDeserializer {
-    fn into_deserializer(&self) -> Deserializer;
-}
+    /// Creates a Deserializer for `term` that uses the given config.
+    pub fn from_term_with_config(term: &'a Term, config: &'a DeserializerConfig) -> Self {
+        Deserializer {
+            term,
+            config,
+            warnings: None,
+            path: None,
+            depth: 0,
+        }
+    }
+
+    // Creates a Deserializer for a term nested inside this one, that shares
+    // this deserializers settings.  `segment` describes where the term is
+    // within this one, and is only called when we're tracking the path.
+    //
+    // Fails if the term is nested deeper than the config allows, which bounds
+    // how deeply we recurse.  The term has already been decoded by then.
+    fn nested<F>(&self, term: &'a Term, segment: F) -> Result<Self>
+    where
+        F: FnOnce() -> Segment,
+    {
+        if self.depth >= self.config.max_depth {
+            return Err(Error::DepthLimitExceeded);
+        }
+        if let Some(path) = self.path {
+            let mut path = path.borrow_mut();
+            path.truncate(self.depth);
+            path.push(PathSegment {
+                segment: segment(),
+                kind: term_kind(term),
+            });
+        }
+        Ok(Deserializer {
+            term,
+            depth: self.depth + 1,
+            ..*self
+        })
+    }
 
-impl IntoEetfDeserializer for Term {
-    fn into_deserializer(&self) -> Deserializer {
-        Deserializer::from_term(self)
+    // Should be passed the result of deserializing a nested term.  If that
+    // succeeded then any failure from here on isn't within the nested term, so
+    // it's removed from the path.
+    fn leave<T>(&self, result: Result<T>) -> Result<T> {
+        if let (Some(path), Ok(_)) = (self.path, &result) {
+            path.borrow_mut().truncate(self.depth);
+        }
+        result
+    }
+
+    fn warn(&self, warning: Warning) {
+        if let Some(warnings) = self.warnings {
+            warnings.borrow_mut().push(warning);
+        }
+    }
+
+    // Whether a list should be deserialized as a map, because it's a proplist
+    // and the config allows that.
+    fn is_proplist(&self, elements: &[Term]) -> bool {
+        self.config.accept_proplists
+            && elements
+                .iter()
+                .all(|element| proplist_entry(element).is_some())
+    }
+
+    // Whether an atom represents `None` (or unit).
+    fn is_none_atom(&self, name: &str) -> bool {
+        name == "nil"
+            || name == "undefined"
+            || self
+                .config
+                .none_atom
+                .as_ref()
+                .map_or(false, |atom| atom == name)
+    }
+
+    // Whether a term is one of the atoms that represent `None`.
+    fn is_none(&self, term: &Term) -> bool {
+        match term {
+            Term::Atom(atom) => self.is_none_atom(&atom.name),
+            _ => false,
+        }
     }
 }
 
-// impl<'de, 'a: 'de> From<&'a Term> for Deserializer<'de> {
-//     fn from(term: &'a Term) -> Self {
-//         Deserializer::from_term(term)
-//     }
-// }
+/// A lossy conversion that was made while deserializing with
+/// `from_bytes_with_warnings`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Warning {
+    /// An integer didn't fit in the type it was being deserialized into, so it
+    /// was clamped to the closest value that type can hold.
+    IntegerNarrowed { original: String, narrowed: String },
+}
 
 /// Deserializes some EETF from a Read
 pub fn from_reader<R, T>(reader: R) -> Result<T>
@@ -51,79 +178,685 @@ where
     R: Read,
     T: DeserializeOwned,
 {
-    let term = Term::decode(reader)?;
-    let deserializer = Deserializer::from_term(&term);
-    let t = T::deserialize(deserializer)?;
-    Ok(t)
+    from_reader_with_config(reader, &DeserializerConfig::default())
 }
 
 /// Deserializes some EETF from a slice of bytes.
 pub fn from_bytes<T>(bytes: &[u8]) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    from_bytes_with_config(bytes, &DeserializerConfig::default())
+}
+
+/// Deserializes an `eetf::Term` that has already been decoded.
+///
+/// This lets a message be decoded once & inspected before picking the type to
+/// deserialize it into:
+///
+/// ```rust
+/// # #[macro_use]
+/// # extern crate serde_derive;
+/// # extern crate eetf;
+/// # extern crate serde_eetf;
+/// # fn main() {
+/// use eetf::Term;
+/// use serde_eetf::{from_term, to_bytes};
+///
+/// #[derive(Serialize, Deserialize, Debug, PartialEq)]
+/// struct Ping {
+///     seq: u32,
+/// }
+///
+/// # let bytes = to_bytes(&Ping { seq: 1 }).unwrap();
+/// let term = Term::decode(bytes.as_slice()).unwrap();
+/// match term {
+///     Term::Map(_) => assert_eq!(from_term::<Ping>(term).unwrap(), Ping { seq: 1 }),
+///     _ => panic!("unexpected message"),
+/// }
+/// # }
+/// ```
+///
+/// Use `Deserializer::from_term` instead to borrow from the term.
+pub fn from_term<T>(term: Term) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    T::deserialize(Deserializer::from_term(&term))
+}
+
+/// Deserializes some EETF from a Read, with the given config.
+pub fn from_reader_with_config<R, T>(reader: R, config: &DeserializerConfig) -> Result<T>
+where
+    R: Read,
+    T: DeserializeOwned,
+{
+    let term = decode_with_config(reader, config)?;
+    let deserializer = Deserializer::from_term_with_config(&term, config);
+    let t = T::deserialize(deserializer)?;
+    Ok(t)
+}
+
+// Decodes a term, then checks it against the limits in `config` that apply
+// to the term as a whole.
+fn decode_with_config<R>(reader: R, config: &DeserializerConfig) -> Result<Term>
+where
+    R: Read,
+{
+    let term = Term::decode(reader)?;
+    if let Some(max_atoms) = config.max_atoms {
+        if count_atoms(&term) > max_atoms {
+            return Err(Error::TooManyAtoms);
+        }
+    }
+    Ok(term)
+}
+
+/// Deserializes each of the terms in a Read, one after another, and passes
+/// the result for each to `f`.
+///
+/// This is for inputs holding many concatenated terms, such as a large dump
+/// file, and avoids holding all of them in memory at once.  A term that can't
+/// be deserialized into a `T` is passed to `f` as an error and the following
+/// terms are still read.  An error decoding a term stops the iteration, as
+/// the rest of the input can't be trusted.  Returns once the Read is
+/// exhausted.
+pub fn for_each_term<R, T, F>(reader: R, f: F)
+where
+    R: Read,
+    T: DeserializeOwned,
+    F: FnMut(Result<T>),
+{
+    TermStream::new(reader).for_each(f)
+}
+
+/// An iterator that deserializes each of the terms in a Read, one after
+/// another.
+///
+/// Iteration ends once the Read is exhausted in between terms.  Like
+/// `for_each_term`, a term that can't be deserialized into a `T` is yielded as
+/// an error and the following terms are still read, but an error decoding a
+/// term (including running out of input part way through one) is the last
+/// item.
+///
+/// Terms are read straight from the Read, so wrap it in a `BufReader` if
+/// reads are expensive.
+pub struct TermStream<R, T> {
+    reader: R,
+    done: bool,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<R, T> TermStream<R, T>
+where
+    R: Read,
+    T: DeserializeOwned,
+{
+    /// Creates a TermStream that reads terms from `reader`.
+    pub fn new(reader: R) -> Self {
+        TermStream {
+            reader,
+            done: false,
+            marker: PhantomData,
+        }
+    }
+
+    /// Returns the Read, positioned after the last term that was read.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    fn decode_next(&mut self) -> Option<Result<Term>> {
+        // Read the version byte ourselves, so that running out of input in
+        // between terms can be told apart from a truncated term.
+        let mut version = [0];
+        loop {
+            match self.reader.read(&mut version) {
+                Ok(0) => return None,
+                Ok(_) => break,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Some(Err(Error::from(eetf::DecodeError::from(e)))),
+            }
+        }
+        let reader = io::Cursor::new(version).chain(&mut self.reader);
+        Some(Term::decode(reader).map_err(Error::from))
+    }
+}
+
+impl<R, T> Iterator for TermStream<R, T>
+where
+    R: Read,
+    T: DeserializeOwned,
+{
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Result<T>> {
+        if self.done {
+            return None;
+        }
+        match self.decode_next() {
+            Some(Ok(term)) => Some(T::deserialize(Deserializer::from_term(&term))),
+            Some(Err(e)) => {
+                self.done = true;
+                Some(Err(e))
+            }
+            None => {
+                self.done = true;
+                None
+            }
+        }
+    }
+}
+
+/// Deserializes some EETF from a slice of bytes, failing with
+/// `Error::DepthLimitExceeded` if terms are nested more than `max_depth`
+/// deep.
+///
+/// The other functions apply a limit of 128, which can be changed with
+/// `DeserializerConfig::max_depth`.
+pub fn from_bytes_with_limit<T>(bytes: &[u8], max_depth: usize) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    let config = DeserializerConfig {
+        max_depth,
+        ..DeserializerConfig::default()
+    };
+    from_bytes_with_config(bytes, &config)
+}
+
+/// Deserializes some EETF from a Read, failing with `Error::InputTooLarge`
+/// rather than reading more than `max_bytes` of it.
+///
+/// This only limits how many bytes are read, not how much memory decoding
+/// allocates: the decoder may reserve space for a binary or list from its
+/// length prefix before reading it, so a short term claiming to hold a huge
+/// binary can still allocate a lot.  It's also independent of the depth
+/// limit: a term well within `max_bytes` can still be nested too deeply,
+/// which fails with `Error::DepthLimitExceeded` as usual.
+pub fn from_reader_with_limit<R, T>(reader: R, max_bytes: u64) -> Result<T>
+where
+    R: Read,
+    T: DeserializeOwned,
+{
+    let mut limited = LimitedReader {
+        inner: reader,
+        remaining: max_bytes,
+        exceeded: false,
+    };
+    match from_reader_with_config(&mut limited, &DeserializerConfig::default()) {
+        Err(_) if limited.exceeded => Err(Error::InputTooLarge),
+        result => result,
+    }
+}
+
+// A Read that fails, rather than reaching EOF like `io::Take`, once the term
+// being decoded needs more than `remaining` bytes.
+struct LimitedReader<R> {
+    inner: R,
+    remaining: u64,
+    exceeded: bool,
+}
+
+impl<R: Read> Read for LimitedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if self.remaining == 0 {
+            self.exceeded = true;
+            return Err(io::Error::new(io::ErrorKind::Other, "input too large"));
+        }
+        let max = std::cmp::min(buf.len() as u64, self.remaining) as usize;
+        let read = self.inner.read(&mut buf[..max])?;
+        self.remaining -= read as u64;
+        Ok(read)
+    }
+}
+
+/// Deserializes some EETF from a slice of bytes, with the given config.
+pub fn from_bytes_with_config<T>(bytes: &[u8], config: &DeserializerConfig) -> Result<T>
 where
     T: DeserializeOwned,
 {
     let cursor = io::Cursor::new(bytes);
 
-    from_reader(cursor)
+    from_reader_with_config(cursor, config)
+}
+
+/// Deserializes some EETF from a slice of bytes that doesn't start with the
+/// version byte.
+///
+/// This is for terms produced by `to_bytes_no_version`, or taken from the
+/// parts of the distribution protocol that omit the version byte.  Terms from
+/// `term_to_binary/1` include it, so should be passed to `from_bytes`.
+pub fn from_bytes_no_version<T>(bytes: &[u8]) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    from_reader(io::Cursor::new([VERSION]).chain(bytes))
+}
+
+/// Deserializes the first term in a slice of bytes, returning it along with
+/// the number of bytes it took up.
+///
+/// Any bytes after the term are ignored, so this can be used to work through
+/// a buffer holding several terms back to back.
+pub fn from_bytes_with_trailing<T>(bytes: &[u8]) -> Result<(T, usize)>
+where
+    T: DeserializeOwned,
+{
+    let mut cursor = io::Cursor::new(bytes);
+    let value = from_reader(&mut cursor)?;
+    Ok((value, cursor.position() as usize))
+}
+
+/// Deserializes some EETF from a slice of bytes, making lossy conversions
+/// rather than failing where possible.
+///
+/// A `Warning` describing each lossy conversion is pushed onto `warnings`.
+/// Useful for migrating data where getting something out is more important
+/// than getting it exactly right.
+pub fn from_bytes_with_warnings<T>(bytes: &[u8], warnings: &mut Vec<Warning>) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    from_bytes_with_warnings_with_config(bytes, warnings, &DEFAULT_DESERIALIZER_CONFIG)
+}
+
+/// The same as `from_bytes_with_warnings`, with the given config.
+pub fn from_bytes_with_warnings_with_config<T>(
+    bytes: &[u8],
+    warnings: &mut Vec<Warning>,
+    config: &DeserializerConfig,
+) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    let term = decode_with_config(io::Cursor::new(bytes), config)?;
+    let sink = RefCell::new(Vec::new());
+    let result = T::deserialize(Deserializer {
+        warnings: Some(&sink),
+        ..Deserializer::from_term_with_config(&term, config)
+    });
+    warnings.extend(sink.into_inner());
+    result
+}
+
+/// Deserializes a tuple struct from some EETF holding a map, using the
+/// entries named by `fields` as the elements of the tuple, in order.
+///
+/// This bridges Erlang maps with named entries to positional rust types, e.g.
+/// `#{x => 1, y => 2}` can become `Point(1, 2)` given the fields `["x", "y"]`.
+/// Entries can be keyed by atoms or binaries, and any entries not named in
+/// `fields` are ignored.
+pub fn from_bytes_by_field_order<T>(bytes: &[u8], fields: &[&str]) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    from_bytes_by_field_order_with_config(bytes, fields, &DEFAULT_DESERIALIZER_CONFIG)
+}
+
+/// The same as `from_bytes_by_field_order`, with the given config.
+pub fn from_bytes_by_field_order_with_config<T>(
+    bytes: &[u8],
+    fields: &[&str],
+    config: &DeserializerConfig,
+) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    let entries = match decode_with_config(io::Cursor::new(bytes), config)? {
+        Term::Map(map) => map.entries,
+        other => return Err(type_mismatch("map", &other)),
+    };
+    let mut elements = Vec::with_capacity(fields.len());
+    for field in fields {
+        let value = entries.iter().find(|(key, _)| match key {
+            Term::Atom(atom) => atom.name == *field,
+            Term::Binary(binary) => binary.bytes == field.as_bytes(),
+            _ => false,
+        });
+        match value {
+            Some((_, value)) => elements.push(value.clone()),
+            None => return Err(Error::Message(format!("missing field `{}`", field))),
+        }
+    }
+    let tuple = Term::Tuple(eetf::Tuple::from(elements));
+    T::deserialize(Deserializer::from_term_with_config(&tuple, config))
+}
+
+/// Attempts to deserialize some EETF into a `T`, and describes why that
+/// failed.
+///
+/// This is intended for debugging mismatches between a rust type & the terms
+/// another node is sending, e.g.
+/// ``at `config.timeout`: Expected integer, got binary: <<"5s">>``.
+pub fn explain_mismatch<T>(bytes: &[u8]) -> String
+where
+    T: DeserializeOwned,
+{
+    explain_mismatch_with_config::<T>(bytes, &DEFAULT_DESERIALIZER_CONFIG)
+}
+
+/// The same as `explain_mismatch`, with the given config.
+pub fn explain_mismatch_with_config<T>(bytes: &[u8], config: &DeserializerConfig) -> String
+where
+    T: DeserializeOwned,
+{
+    let term = match decode_with_config(io::Cursor::new(bytes), config) {
+        Ok(term) => term,
+        Err(e) => return format!("could not decode term: {}", e),
+    };
+    let path = RefCell::new(Vec::new());
+    let result = T::deserialize(Deserializer {
+        path: Some(&path),
+        ..Deserializer::from_term_with_config(&term, config)
+    });
+    let error = match result {
+        Ok(_) => return "no mismatch".to_string(),
+        Err(error) => error,
+    };
+
+    let path = path.into_inner();
+    // Type mismatches already say what was found.
+    let found = match (&error, path.last()) {
+        (Error::TypeMismatch { .. }, _) => String::new(),
+        (_, Some(segment)) => format!(" (found {})", segment.kind),
+        (_, None) => format!(" (found {})", term_kind(&term)),
+    };
+    if path.is_empty() {
+        format!("at the top level: {}{}", error, found)
+    } else {
+        format!("at `{}`: {}{}", format_path(&path), error, found)
+    }
+}
+
+// Identifies a term nested inside another.
+enum Segment {
+    Field(String),
+    Index(usize),
+}
+
+impl Segment {
+    // Describes a map key or variant tag.
+    fn from_key(key: &Term) -> Segment {
+        Segment::Field(key_name(key))
+    }
+}
+
+// The name of a map key or variant tag if it has one, or else the key itself.
+fn key_name(key: &Term) -> String {
+    match key {
+        Term::Atom(atom) => atom.name.clone(),
+        Term::Binary(binary) => match str::from_utf8(&binary.bytes) {
+            Ok(name) => name.to_string(),
+            Err(_) => key.to_string(),
+        },
+        _ => key.to_string(),
+    }
+}
+
+struct PathSegment {
+    segment: Segment,
+    kind: &'static str,
+}
+
+fn format_path(path: &[PathSegment]) -> String {
+    let mut formatted = String::new();
+    for (i, PathSegment { segment, .. }) in path.iter().enumerate() {
+        match segment {
+            Segment::Field(name) if i == 0 => formatted.push_str(name),
+            Segment::Field(name) => {
+                formatted.push('.');
+                formatted.push_str(name);
+            }
+            Segment::Index(index) => formatted.push_str(&format!("[{}]", index)),
+        }
+    }
+    formatted
+}
+
+// Counts the atoms in a term, including repeats.  This avoids recursion, as
+// it's used on terms that may be hostile.
+fn count_atoms(term: &Term) -> usize {
+    let mut count = 0;
+    let mut pending = vec![term];
+    while let Some(term) = pending.pop() {
+        match term {
+            Term::Atom(_) => count += 1,
+            Term::List(list) => pending.extend(&list.elements),
+            Term::ImproperList(list) => {
+                pending.extend(&list.elements);
+                pending.push(&list.last);
+            }
+            Term::Tuple(tuple) => pending.extend(&tuple.elements),
+            Term::Map(map) => {
+                for (key, value) in &map.entries {
+                    pending.push(key);
+                    pending.push(value);
+                }
+            }
+            _ => (),
+        }
+    }
+    count
+}
+
+// Parses a charlist, i.e. a list of integer code points.
+fn parse_charlist(elements: &[Term]) -> Result<String> {
+    elements
+        .iter()
+        .map(|element| match element {
+            Term::FixInteger(int) if int.value >= 0 => {
+                std::char::from_u32(int.value as u32).ok_or(Error::ExpectedBinary)
+            }
+            _ => Err(Error::ExpectedBinary),
+        })
+        .collect()
+}
+
+// Parses a list of integers that each fit in a byte.
+fn parse_byte_list(elements: &[Term]) -> Result<Vec<u8>> {
+    elements
+        .iter()
+        .map(|element| match element {
+            Term::FixInteger(int) => {
+                u8::try_from(int.value).map_err(|_| type_mismatch("byte", element))
+            }
+            _ => Err(type_mismatch("byte", element)),
+        })
+        .collect()
+}
+
+// The error for a term that isn't of the expected type.
+fn type_mismatch(expected: &'static str, term: &Term) -> Error {
+    Error::TypeMismatch {
+        expected,
+        got: term_kind(term),
+        term: inspect(term),
+    }
+}
+
+// The most characters of a term to include in an error.
+const MAX_INSPECT_LEN: usize = 80;
+
+// Formats a term for an error message, truncating it if it's long.
+fn inspect(term: &Term) -> String {
+    let mut writer = TruncatingWriter {
+        formatted: String::new(),
+        len: 0,
+    };
+    if write!(writer, "{}", term).is_err() {
+        writer.formatted.push_str("...");
+    }
+    writer.formatted
+}
+
+// Keeps the first MAX_INSPECT_LEN chars written to it, then fails, so that
+// formatting a huge term stops early rather than building the whole string.
+struct TruncatingWriter {
+    formatted: String,
+    len: usize,
+}
+
+impl fmt::Write for TruncatingWriter {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for c in s.chars() {
+            if self.len == MAX_INSPECT_LEN {
+                return Err(fmt::Error);
+            }
+            self.formatted.push(c);
+            self.len += 1;
+        }
+        Ok(())
+    }
+}
+
+// A short description of the kind of a term, for error messages.
+fn term_kind(term: &Term) -> &'static str {
+    match term {
+        Term::Atom(_) => "atom",
+        Term::FixInteger(_) | Term::BigInteger(_) => "integer",
+        Term::Float(_) => "float",
+        Term::Pid(_) => "pid",
+        Term::Port(_) => "port",
+        Term::Reference(_) => "reference",
+        Term::ExternalFun(_) | Term::InternalFun(_) => "function",
+        Term::Binary(_) => "binary",
+        Term::BitBinary(_) => "bitstring",
+        Term::List(_) => "list",
+        Term::ImproperList(_) => "improper list",
+        Term::Tuple(_) => "tuple",
+        Term::Map(_) => "map",
+    }
 }
 
 // Implementation methods for deserializer that require a lifetime.
 impl<'a> Deserializer<'a> {
     fn parse_integer<T>(&self) -> Result<T>
     where
-        T: FromPrimitive,
+        T: FromPrimitive + Bounded + ToString,
     {
-        match self.term {
-            Term::FixInteger(fix_int) => {
-                if let Some(num) = T::from_i32(fix_int.value) {
-                    Ok(num)
-                } else {
-                    Err(Error::IntegerConvertError)
+        let (num, negative) = match self.term {
+            Term::FixInteger(fix_int) => (T::from_i32(fix_int.value), fix_int.value < 0),
+            // Values above i64::MAX can only fit into a u64.
+            Term::BigInteger(big_int) => (
+                big_int
+                    .value
+                    .to_i64()
+                    .and_then(T::from_i64)
+                    .or_else(|| big_int.value.to_u64().and_then(T::from_u64)),
+                big_int.value.is_negative(),
+            ),
+            // Some Erlang code sends 1.0 where 1 is expected.
+            Term::Float(float) if self.config.float_to_integer == FloatToInteger::AcceptWhole => {
+                if float.value.fract() != 0.0 {
+                    return Err(de::Error::invalid_value(
+                        de::Unexpected::Float(float.value),
+                        &"a float with no fractional part",
+                    ));
                 }
+                (T::from_f64(float.value), float.value < 0.0)
             }
-            Term::BigInteger(big_int) => {
-                if let Some(num) = big_int.to_i64() {
-                    if let Some(num) = T::from_i64(num) {
-                        Ok(num)
-                    } else {
-                        Err(Error::IntegerConvertError)
-                    }
+            _ => return Err(type_mismatch("integer", self.term)),
+        };
+        match num {
+            Some(num) => Ok(num),
+            None if self.warnings.is_some() => {
+                let narrowed = if negative {
+                    T::min_value()
                 } else {
-                    Err(Error::IntegerConvertError)
-                }
+                    T::max_value()
+                };
+                self.warn(Warning::IntegerNarrowed {
+                    original: self.term.to_string(),
+                    narrowed: narrowed.to_string(),
+                });
+                Ok(narrowed)
             }
-            _ => Err(Error::ExpectedFixInteger),
+            None => Err(Error::IntegerConvertError),
         }
     }
 
+    // Creates an EnumDeserializer for a tagged value.  If the tag isn't one of
+    // `variants` and the enum has the configured catch-all variant, that
+    // variant gets the whole term instead.
+    fn enum_deserializer(
+        &self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        variant: &'a Term,
+        term: &'a Term,
+    ) -> EnumDeserializer<'a> {
+        let has_variant = |variant: &str| variants.iter().any(|v| *v == variant);
+        let config = self.config;
+        if let Some(catch_all) = &config.catch_all_variant {
+            let known = match variant_name(variant, name, config.variant_case, variants) {
+                Some(variant) => has_variant(variant.as_str()),
+                None => false,
+            };
+            if !known && has_variant(catch_all.as_str()) {
+                return EnumDeserializer {
+                    parent: *self,
+                    name,
+                    variant,
+                    variants,
+                    term: self.term,
+                    catch_all: Some(catch_all.as_str()),
+                    flat: None,
+                };
+            }
+        }
+        EnumDeserializer::new(*self, name, variant, variants, term)
+    }
+
     fn parse_float<T>(&self) -> Result<T>
     where
         T: FromPrimitive,
     {
-        match self.term {
-            Term::Float(float) => {
-                if let Some(num) = T::from_f64(float.value) {
-                    Ok(num)
-                } else {
-                    Err(Error::IntegerConvertError)
+        let value = match self.term {
+            Term::Float(float) => float.value,
+            // Floats may have been serialized as strings to preserve them
+            // exactly.
+            Term::Binary(_) => {
+                let string = self.parse_str()?;
+                match string.parse() {
+                    Ok(value) => value,
+                    Err(_) => {
+                        return Err(de::Error::invalid_value(
+                            de::Unexpected::Str(string),
+                            &"a float",
+                        ))
+                    }
                 }
             }
-            _ => Err(Error::ExpectedFloat),
+            // As serialized with NanHandling::Atom.
+            Term::Atom(atom) => match atom.name.as_str() {
+                "nan" => std::f64::NAN,
+                "infinity" => std::f64::INFINITY,
+                "neg_infinity" => std::f64::NEG_INFINITY,
+                _ => return Err(type_mismatch("float", self.term)),
+            },
+            _ => return Err(type_mismatch("float", self.term)),
+        };
+        if let Some(num) = T::from_f64(value) {
+            Ok(num)
+        } else {
+            Err(Error::IntegerConvertError)
         }
     }
 
-    fn parse_binary(&self) -> Result<&[u8]> {
+    fn parse_binary(&self) -> Result<&'a [u8]> {
         match self.term {
             Term::Binary(binary) => Ok(&binary.bytes),
-            _ => Err(Error::ExpectedBinary),
+            _ => Err(type_mismatch("binary", self.term)),
         }
     }
 
-    fn parse_string(&self) -> Result<String> {
+    fn parse_str(&self) -> Result<&'a str> {
         match self.parse_binary() {
-            Ok(bytes) => str::from_utf8(&bytes)
-                .map(|s| s.to_string())
-                .or(Err(Error::Utf8DecodeError)),
+            Ok(bytes) => str::from_utf8(bytes).or(Err(Error::Utf8DecodeError)),
             Err(e) => Err(e),
         }
     }
@@ -132,11 +865,59 @@ impl<'a> Deserializer<'a> {
 impl<'de, 'a: 'de> de::Deserializer<'de> for Deserializer<'a> {
     type Error = Error;
 
-    fn deserialize_any<V>(self, _visitor: V) -> Result<V::Value>
+    // Used when the type being deserialized wants to decide what to do based on
+    // the shape of the data.  Note that serde has no way to tell a visitor
+    // whether a sequence was a tuple or a list, so both are visited as a seq.
+    //
+    // Erlang booleans are just atoms, so as a heuristic the atoms true & false
+    // are visited as bools here.  Types that ask for a string or an enum still
+    // get the atom's name.
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        Err(Error::TypeHintsRequired)
+        match self.term {
+            Term::Atom(atom) if atom.name == "true" => visitor.visit_bool(true),
+            Term::Atom(atom) if atom.name == "false" => visitor.visit_bool(false),
+            Term::Atom(atom) => visitor.visit_string(atom.name.clone()),
+            Term::FixInteger(fix_int) => visitor.visit_i32(fix_int.value),
+            Term::BigInteger(big_int) => {
+                if let Some(num) = big_int.value.to_i64() {
+                    visitor.visit_i64(num)
+                } else if let Some(num) = big_int.value.to_u64() {
+                    visitor.visit_u64(num)
+                } else {
+                    Err(Error::IntegerConvertError)
+                }
+            }
+            Term::Float(float) => visitor.visit_f64(float.value),
+            Term::Binary(binary) => match self.config.binary_as {
+                BinaryInterpretation::StringIfUtf8 => match str::from_utf8(&binary.bytes) {
+                    Ok(string) => visitor.visit_borrowed_str(string),
+                    Err(_) => visitor.visit_borrowed_bytes(&binary.bytes),
+                },
+                BinaryInterpretation::Bytes => visitor.visit_borrowed_bytes(&binary.bytes),
+            },
+            Term::List(list) => visitor.visit_seq(ListDeserializer::new(self, &list.elements)),
+            // The tail is visited as the last element.  Use ImproperList to
+            // tell it apart from the rest.
+            Term::ImproperList(list) => {
+                visitor.visit_seq(ListDeserializer::improper(self, &list.elements, &list.last))
+            }
+            Term::Tuple(tuple) => visitor.visit_seq(ListDeserializer::new(self, &tuple.elements)),
+            Term::Map(_) => self.deserialize_map(visitor),
+            // Pids, references, ports & bitstrings have no equivalent in the
+            // serde data model, so are visited as their encoded bytes, which
+            // can be decoded back into the term.
+            Term::Pid(_) | Term::Reference(_) | Term::Port(_) | Term::BitBinary(_) => {
+                let mut bytes = Vec::new();
+                self.term.encode(&mut bytes)?;
+                visitor.visit_byte_buf(bytes)
+            }
+            // Functions etc. have no equivalent either, and aren't expected in
+            // messages.
+            _ => Err(Error::TypeHintsRequired),
+        }
     }
 
     fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value>
@@ -153,8 +934,13 @@ impl<'de, 'a: 'de> de::Deserializer<'de> for Deserializer<'a> {
                     Err(Error::InvalidBoolean)
                 }
             }
-
-            _ => Err(Error::ExpectedBoolean),
+            // Some C-nodes represent booleans as integers.
+            Term::FixInteger(fix_int) => match fix_int.value {
+                1 => visitor.visit_bool(true),
+                0 => visitor.visit_bool(false),
+                _ => Err(Error::InvalidBoolean),
+            },
+            _ => Err(type_mismatch("boolean", self.term)),
         }
     }
 
@@ -186,18 +972,49 @@ impl<'de, 'a: 'de> de::Deserializer<'de> for Deserializer<'a> {
         visitor.visit_i64(self.parse_integer()?)
     }
 
-    fn deserialize_u8<V>(self, visitor: V) -> Result<V::Value>
+    fn deserialize_i128<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_u8(self.parse_integer()?)
+        let num = match self.term {
+            Term::FixInteger(fix_int) => i128::from(fix_int.value),
+            Term::BigInteger(big_int) => match big_int.value.to_i128() {
+                Some(num) => num,
+                None => return Err(Error::IntegerConvertError),
+            },
+            _ => return Err(type_mismatch("integer", self.term)),
+        };
+        visitor.visit_i128(num)
     }
 
-    fn deserialize_u16<V>(self, visitor: V) -> Result<V::Value>
+    fn deserialize_u128<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_u16(self.parse_integer()?)
+        let num = match self.term {
+            Term::FixInteger(fix_int) if fix_int.value >= 0 => fix_int.value as u128,
+            Term::FixInteger(_) => return Err(Error::IntegerConvertError),
+            Term::BigInteger(big_int) => match big_int.value.to_u128() {
+                Some(num) => num,
+                None => return Err(Error::IntegerConvertError),
+            },
+            _ => return Err(type_mismatch("integer", self.term)),
+        };
+        visitor.visit_u128(num)
+    }
+
+    fn deserialize_u8<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_u8(self.parse_integer()?)
+    }
+
+    fn deserialize_u16<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_u16(self.parse_integer()?)
     }
 
     fn deserialize_u32<V>(self, visitor: V) -> Result<V::Value>
@@ -232,14 +1049,25 @@ impl<'de, 'a: 'de> de::Deserializer<'de> for Deserializer<'a> {
     where
         V: Visitor<'de>,
     {
-        match self.parse_string() {
-            Err(Error::ExpectedBinary) => Err(Error::ExpectedChar),
+        // Erlang represents characters as their code points, e.g. `$A` is 65.
+        if let Term::FixInteger(int) = self.term {
+            return match std::char::from_u32(int.value as u32) {
+                Some(c) if int.value >= 0 => visitor.visit_char(c),
+                _ => Err(Error::ExpectedChar),
+            };
+        }
+        match self.parse_str() {
+            Err(Error::TypeMismatch { got, term, .. }) => Err(Error::TypeMismatch {
+                expected: "char",
+                got,
+                term,
+            }),
             Err(other) => Err(other),
             Ok(string) => {
-                if string.len() == 1 {
-                    visitor.visit_char(string.chars().next().unwrap())
-                } else {
-                    Err(Error::ExpectedChar)
+                let mut chars = string.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => visitor.visit_char(c),
+                    _ => Err(Error::ExpectedChar),
                 }
             }
         }
@@ -249,7 +1077,13 @@ impl<'de, 'a: 'de> de::Deserializer<'de> for Deserializer<'a> {
     where
         V: Visitor<'de>,
     {
-        visitor.visit_string(self.parse_string()?)
+        match self.term {
+            // Erlang strings are often charlists rather than binaries.
+            Term::List(list) => visitor.visit_string(parse_charlist(&list.elements)?),
+            // Map keys may have been serialized as atoms by MapKeyFormat.
+            Term::Atom(atom) => visitor.visit_borrowed_str(&atom.name),
+            _ => visitor.visit_borrowed_str(self.parse_str()?),
+        }
     }
 
     fn deserialize_string<V>(self, visitor: V) -> Result<V::Value>
@@ -263,14 +1097,18 @@ impl<'de, 'a: 'de> de::Deserializer<'de> for Deserializer<'a> {
     where
         V: Visitor<'de>,
     {
-        visitor.visit_bytes(self.parse_binary()?)
+        match self.term {
+            // Erlang code may send bytes as a list rather than a binary.
+            Term::List(list) => visitor.visit_byte_buf(parse_byte_list(&list.elements)?),
+            _ => visitor.visit_borrowed_bytes(self.parse_binary()?),
+        }
     }
 
     fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_bytes(self.parse_binary()?)
+        self.deserialize_bytes(visitor)
     }
 
     fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
@@ -278,13 +1116,7 @@ impl<'de, 'a: 'de> de::Deserializer<'de> for Deserializer<'a> {
         V: Visitor<'de>,
     {
         match self.term {
-            Term::Atom(atom) => {
-                if atom.name == "nil" {
-                    visitor.visit_none()
-                } else {
-                    visitor.visit_some(self)
-                }
-            }
+            Term::Atom(atom) if self.is_none_atom(&atom.name) => visitor.visit_none(),
             _ => visitor.visit_some(self),
         }
     }
@@ -294,14 +1126,11 @@ impl<'de, 'a: 'de> de::Deserializer<'de> for Deserializer<'a> {
         V: Visitor<'de>,
     {
         match self.term {
-            Term::Atom(atom) => {
-                if atom.name == "nil" {
-                    visitor.visit_unit()
-                } else {
-                    Err(Error::ExpectedNil)
-                }
-            }
-            _ => Err(Error::ExpectedNil),
+            Term::Atom(atom) if self.is_none_atom(&atom.name) => visitor.visit_unit(),
+            // Any of the representations UnitFormat can choose.
+            Term::Tuple(tuple) if tuple.elements.is_empty() => visitor.visit_unit(),
+            Term::List(list) if list.elements.is_empty() => visitor.visit_unit(),
+            _ => Err(type_mismatch("nil", self.term)),
         }
     }
 
@@ -312,11 +1141,43 @@ impl<'de, 'a: 'de> de::Deserializer<'de> for Deserializer<'a> {
         self.deserialize_unit(visitor)
     }
 
-    fn deserialize_newtype_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value>
+    fn deserialize_newtype_struct<V>(self, name: &'static str, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_newtype_struct(self)
+        if name == value::TOKEN {
+            value::pass_term(self.term.clone());
+            return visitor.visit_unit();
+        }
+        if name == atom::TOKEN {
+            return match self.term {
+                Term::Atom(atom) => visitor.visit_str(&atom.name),
+                _ => Err(type_mismatch("atom", self.term)),
+            };
+        }
+        match self.config.newtype_struct_format {
+            NewtypeStructFormat::Transparent => visitor.visit_newtype_struct(self),
+            NewtypeStructFormat::Tagged => {
+                let elements = match self.term {
+                    Term::Tuple(tuple) if tuple.elements.len() == 2 => &tuple.elements,
+                    Term::Tuple(_) => return Err(Error::WrongTupleLength),
+                    _ => return Err(type_mismatch("tuple", self.term)),
+                };
+                let tag = name.to_snake_case();
+                match &elements[0] {
+                    Term::Atom(atom) if atom.name == tag => (),
+                    _ => {
+                        return Err(Error::Message(format!(
+                            "expected a newtype struct tagged with `{}`",
+                            tag
+                        )))
+                    }
+                }
+                let result =
+                    visitor.visit_newtype_struct(self.nested(&elements[1], || Segment::Index(1))?);
+                self.leave(result)
+            }
+        }
     }
 
     // Deserialization of compound types like sequences and maps happens by
@@ -328,14 +1189,19 @@ impl<'de, 'a: 'de> de::Deserializer<'de> for Deserializer<'a> {
     {
         match self.term {
             Term::List(list) => {
-                let seq_deserializer = ListDeserializer::new(list.elements.iter());
+                let seq_deserializer = ListDeserializer::new(self, &list.elements);
                 visitor.visit_seq(seq_deserializer)
                 // TODO: Figure out how to call end here.
             }
-            other => {
-                eprintln!("{}", other);
-                Err(Error::ExpectedList)
+            // Lets binaries be deserialized into a Vec<u8>.
+            Term::Binary(binary) => {
+                let mut seq_deserializer: de::value::SeqDeserializer<_, Error> =
+                    de::value::SeqDeserializer::new(binary.bytes.iter().cloned());
+                let result = visitor.visit_seq(&mut seq_deserializer)?;
+                seq_deserializer.end()?;
+                Ok(result)
             }
+            other => Err(type_mismatch("list", other)),
         }
     }
 
@@ -348,24 +1214,32 @@ impl<'de, 'a: 'de> de::Deserializer<'de> for Deserializer<'a> {
                 if tuple.elements.len() != len {
                     return Err(Error::WrongTupleLength);
                 }
-                let seq_deserializer = ListDeserializer::new(tuple.elements.iter());
+                let seq_deserializer = ListDeserializer::new(self, &tuple.elements);
                 visitor.visit_seq(seq_deserializer)
                 // TODO: Figure out how to call end here.
             }
-            _ => Err(Error::ExpectedTuple),
+            _ => Err(type_mismatch("tuple", self.term)),
         }
     }
 
-    // Tuple structs look just like tuples in EETF.
+    // Tuple structs look just like tuples in EETF, though they may be tagged
+    // with their name like a record.
     fn deserialize_tuple_struct<V>(
         self,
-        _name: &'static str,
+        name: &'static str,
         len: usize,
         visitor: V,
     ) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
+        if let Term::Tuple(tuple) = self.term {
+            if let Some((Term::Atom(tag), elements)) = tuple.elements.split_first() {
+                if elements.len() == len && tag.name == name.to_snake_case() {
+                    return visitor.visit_seq(ListDeserializer::new(self, elements));
+                }
+            }
+        }
         self.deserialize_tuple(len, visitor)
     }
 
@@ -373,47 +1247,61 @@ impl<'de, 'a: 'de> de::Deserializer<'de> for Deserializer<'a> {
     where
         V: Visitor<'de>,
     {
-        match self.term {
-            Term::Map(map) => {
-                let mut map_deserializer = MapDeserializer::new(map.entries.iter());
-                visitor.visit_map(&mut map_deserializer).and_then(|result| {
-                    match map_deserializer.end() {
-                        Ok(()) => Ok(result),
-                        Err(e) => Err(e),
-                    }
-                })
+        let mut map_deserializer = match self.term {
+            Term::Map(map) => MapDeserializer::new(self, &map.entries),
+            // Empty maps may have been serialized as nil or an empty list.
+            Term::List(list) if list.elements.is_empty() => MapDeserializer::new(self, &[]),
+            Term::Atom(atom) if self.config.accept_nil_maps && self.is_none_atom(&atom.name) => {
+                MapDeserializer::new(self, &[])
             }
-            _ => Err(Error::ExpectedMap),
-        }
+            Term::List(list) if self.is_proplist(&list.elements) => {
+                MapDeserializer::proplist(self, &list.elements)
+            }
+            _ => return Err(type_mismatch("map", self.term)),
+        };
+        let result = visitor.visit_map(&mut map_deserializer)?;
+        map_deserializer.end()?;
+        Ok(result)
     }
 
+    // Structs are usually maps, but may have been serialized as records.
     fn deserialize_struct<V>(
         self,
-        _name: &'static str,
-        _fields: &'static [&'static str],
+        name: &'static str,
+        fields: &'static [&'static str],
         visitor: V,
     ) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        match self.term {
-            Term::Map(map) => {
-                let mut map_deserializer = MapDeserializer::new(map.entries.iter());
-                visitor.visit_map(&mut map_deserializer).and_then(|result| {
-                    match map_deserializer.end() {
-                        Ok(()) => Ok(result),
-                        Err(e) => Err(e),
-                    }
-                })
+        let mut map_deserializer = match self.term {
+            Term::Map(map) => MapDeserializer::new(self, &map.entries),
+            Term::Tuple(tuple) => match tuple.elements.split_first() {
+                Some((Term::Atom(tag), elements))
+                    if elements.len() == fields.len() && tag.name == name.to_snake_case() =>
+                {
+                    return visitor.visit_seq(ListDeserializer::new(self, elements));
+                }
+                // Otherwise an untagged tuple holds the fields in order.
+                _ if tuple.elements.len() == fields.len() => {
+                    return visitor.visit_seq(ListDeserializer::new(self, &tuple.elements));
+                }
+                _ => return Err(Error::WrongTupleLength),
+            },
+            Term::List(list) if self.is_proplist(&list.elements) => {
+                MapDeserializer::proplist(self, &list.elements)
             }
-            _ => Err(Error::ExpectedMap),
-        }
+            _ => return Err(type_mismatch("map", self.term)),
+        };
+        let result = visitor.visit_map(&mut map_deserializer)?;
+        map_deserializer.end()?;
+        Ok(result)
     }
 
     fn deserialize_enum<V>(
         self,
-        _name: &'static str,
-        _variants: &'static [&'static str],
+        name: &'static str,
+        variants: &'static [&'static str],
         visitor: V,
     ) -> Result<V::Value>
     where
@@ -422,15 +1310,45 @@ impl<'de, 'a: 'de> de::Deserializer<'de> for Deserializer<'a> {
         match self.term {
             Term::Atom(atom) => {
                 // We have a unit variant.
-                visitor.visit_enum(atom.name.to_camel_case().into_deserializer())
+                visitor.visit_enum(
+                    variant_from_tag(&atom.name, name, self.config.variant_case, variants)
+                        .into_deserializer(),
+                )
             }
-            Term::Tuple(tuple) => match tuple.elements.as_slice() {
-                [variant_term, value_term] => {
-                    visitor.visit_enum(EnumDeserializer::new(&variant_term, &value_term))
+            Term::Tuple(tuple) if self.config.variant_format == VariantFormat::FlatTuple => {
+                match tuple.elements.split_first() {
+                    Some((variant_term, elements)) => {
+                        let mut access =
+                            self.enum_deserializer(name, variants, variant_term, self.term);
+                        // A catch-all variant is given the whole tuple.
+                        if access.catch_all.is_none() {
+                            access.flat = Some(elements);
+                        }
+                        visitor.visit_enum(access)
+                    }
+                    None => Err(Error::MisSizedVariantTuple),
                 }
+            }
+            Term::Tuple(tuple) => match tuple.elements.as_slice() {
+                [variant_term, value_term] => visitor.visit_enum(self.enum_deserializer(
+                    name,
+                    variants,
+                    variant_term,
+                    value_term,
+                )),
+                _ => Err(Error::MisSizedVariantTuple),
+            },
+            // Some encoders use a [Tag, Value] list rather than a tuple.
+            Term::List(list) => match list.elements.as_slice() {
+                [variant_term, value_term] => visitor.visit_enum(self.enum_deserializer(
+                    name,
+                    variants,
+                    variant_term,
+                    value_term,
+                )),
                 _ => Err(Error::MisSizedVariantTuple),
             },
-            _ => Err(Error::ExpectedAtomOrTuple),
+            _ => Err(type_mismatch("atom or tuple", self.term)),
         }
     }
 
@@ -439,8 +1357,15 @@ impl<'de, 'a: 'de> de::Deserializer<'de> for Deserializer<'a> {
         V: Visitor<'de>,
     {
         match self.term {
-            Term::Atom(atom) => visitor.visit_string(atom.name.clone()),
-            _ => Err(Error::ExpectedAtom),
+            // Field names are borrowed from the term, rather than cloned for
+            // every field of every struct.
+            Term::Atom(atom) => visitor.visit_borrowed_str(&atom.name),
+            // Field names may have been serialized as binaries.
+            Term::Binary(_) => visitor.visit_borrowed_str(self.parse_str()?),
+            // Serde identifies fields by their index when given an integer.
+            Term::FixInteger(int) if int.value >= 0 => visitor.visit_u64(int.value as u64),
+            Term::FixInteger(int) => visitor.visit_i64(i64::from(int.value)),
+            _ => Err(type_mismatch("atom", self.term)),
         }
     }
 
@@ -448,72 +1373,128 @@ impl<'de, 'a: 'de> de::Deserializer<'de> for Deserializer<'a> {
     where
         V: Visitor<'de>,
     {
-        // Just skip over this by calling visit_unit.
+        // The term has already been decoded, so there's nothing to skip over.
+        // Visiting unit means we don't recurse into compound terms either.
         visitor.visit_unit()
     }
 }
 
-struct ListDeserializer<I>
-where
-    I: Iterator,
-{
-    iter: iter::Fuse<I>,
+struct ListDeserializer<'a> {
+    parent: Deserializer<'a>,
+    iter: slice::Iter<'a, Term>,
+    // The tail of an improper list, which follows the elements.
+    tail: Option<&'a Term>,
+    index: usize,
 }
 
-impl<I> ListDeserializer<I>
-where
-    I: Iterator,
-{
-    fn new(iter: I) -> Self {
-        ListDeserializer { iter: iter.fuse() }
+impl<'a> ListDeserializer<'a> {
+    fn new(parent: Deserializer<'a>, elements: &'a [Term]) -> Self {
+        ListDeserializer {
+            parent,
+            iter: elements.iter(),
+            tail: None,
+            index: 0,
+        }
+    }
+
+    fn improper(parent: Deserializer<'a>, elements: &'a [Term], tail: &'a Term) -> Self {
+        ListDeserializer {
+            tail: Some(tail),
+            ..ListDeserializer::new(parent, elements)
+        }
     }
 }
 
-impl<'de, 'a: 'de, I> SeqAccess<'de> for ListDeserializer<I>
-where
-    I: Iterator<Item = &'a Term>,
-{
+impl<'de, 'a: 'de> SeqAccess<'de> for ListDeserializer<'a> {
     type Error = Error;
 
     fn next_element_seed<V>(&mut self, seed: V) -> Result<Option<V::Value>>
     where
         V: de::DeserializeSeed<'de>,
     {
-        match self.iter.next() {
-            Some(term) => seed.deserialize(Deserializer::from_term(term)).map(Some),
+        match self.iter.next().or_else(|| self.tail.take()) {
+            Some(term) => {
+                let index = self.index;
+                self.index += 1;
+                let result = seed.deserialize(self.parent.nested(term, || Segment::Index(index))?);
+                self.parent.leave(result).map(Some)
+            }
             None => Ok(None),
         }
     }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len() + self.tail.iter().len())
+    }
 }
 
-// TODO: Look at https://github.com/flavray/avro-rs/blob/master/src/de.rs#L50-L53
-// and figure out if we can use it's ideas to simplify all this lifetime shit.
+// The entries of a map, or of a proplist that's being deserialized as one.
+enum Entries<'a> {
+    Map(slice::Iter<'a, (Term, Term)>),
+    Proplist(slice::Iter<'a, Term>),
+}
 
-struct MapDeserializer<'de, I, T>
-where
-    I: Iterator<Item = T>,
-    T: Pair<'de> + 'de,
-    First<'de, I::Item>: 'de,
-    Second<'de, I::Item>: 'de,
-{
-    items: iter::Fuse<I>,
-    current_value: Option<&'de T::Second>,
+impl<'a> Entries<'a> {
+    fn len(&self) -> usize {
+        match self {
+            Entries::Map(iter) => iter.len(),
+            Entries::Proplist(iter) => iter.len(),
+        }
+    }
 }
 
-impl<'de, I, T> MapDeserializer<'de, I, T>
-where
-    I: Iterator<Item = T>,
-    T: Pair<'de>,
-{
-    fn new(iter: I) -> Self {
+impl<'a> Iterator for Entries<'a> {
+    type Item = (&'a Term, &'a Term);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Entries::Map(iter) => iter.next().map(|(key, value)| (key, value)),
+            Entries::Proplist(iter) => iter.next().and_then(proplist_entry),
+        }
+    }
+}
+
+// Splits an element of a proplist, i.e. a `{Key, Value}` tuple keyed by an
+// atom or binary, into its key & value.
+fn proplist_entry(term: &Term) -> Option<(&Term, &Term)> {
+    match term {
+        Term::Tuple(tuple) if tuple.elements.len() == 2 => match tuple.elements[0] {
+            Term::Atom(_) | Term::Binary(_) => Some((&tuple.elements[0], &tuple.elements[1])),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+struct MapDeserializer<'a> {
+    parent: Deserializer<'a>,
+    entries: Entries<'a>,
+    current_key: Option<&'a Term>,
+    current_value: Option<&'a Term>,
+}
+
+impl<'a> MapDeserializer<'a> {
+    fn new(parent: Deserializer<'a>, entries: &'a [(Term, Term)]) -> Self {
+        MapDeserializer {
+            parent,
+            entries: Entries::Map(entries.iter()),
+            current_key: None,
+            current_value: None,
+        }
+    }
+
+    // Every element of `elements` must be a proplist entry.
+    fn proplist(parent: Deserializer<'a>, elements: &'a [Term]) -> Self {
         MapDeserializer {
-            items: iter.fuse(),
+            parent,
+            entries: Entries::Proplist(elements.iter()),
+            current_key: None,
             current_value: None,
         }
     }
 
     fn end(self) -> Result<()> {
-        if self.items.count() == 0 {
+        if self.entries.len() == 0 {
             Ok(())
         } else {
             Err(Error::TooManyItems)
@@ -521,13 +1502,7 @@ where
     }
 }
 
-impl<'a, 'de: 'a, I, T> MapAccess<'de> for &'a mut MapDeserializer<'de, I, T>
-where
-    I: Iterator<Item = T>,
-    T: Pair<'de>,
-    First<'de, I::Item>: IntoEetfDeserializer,
-    Second<'de, I::Item>: IntoEetfDeserializer,
-{
+impl<'de, 'a: 'de> MapAccess<'de> for MapDeserializer<'a> {
     type Error = Error;
 
     fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
@@ -535,15 +1510,18 @@ where
         K: DeserializeSeed<'de>,
     {
         if self.current_value.is_some() {
-            panic!("MapDeserializer.next_key_seed was called twice in a row")
+            return Err(de::Error::custom(
+                "MapDeserializer.next_key_seed was called twice in a row",
+            ));
         }
 
-        match self.items.next() {
-            Some(pair) => {
-                let (key, val) = pair.split();
-                self.current_value = Some(val);
+        match self.entries.next() {
+            Some((key, value)) => {
+                self.current_key = Some(key);
+                self.current_value = Some(value);
 
-                seed.deserialize(key.into_deserializer()).map(Some)
+                let result = seed.deserialize(self.parent.nested(key, || Segment::from_key(key))?);
+                self.parent.leave(result).map(Some)
             }
             None => Ok(None),
         }
@@ -553,23 +1531,66 @@ where
     where
         V: DeserializeSeed<'de>,
     {
-        if let Some(value) = self.current_value {
+        if let (Some(key), Some(value)) = (self.current_key, self.current_value) {
             self.current_value = None;
-            seed.deserialize(value.into_deserializer())
+            let nested = self.parent.nested(value, || Segment::from_key(key))?;
+            let result = match seed.deserialize(nested) {
+                // Erlang code often leaves unset entries as nil or undefined,
+                // which would otherwise give a confusing error from whatever
+                // type the entry should have had.
+                Err(_) if self.parent.is_none(value) => Err(Error::UnexpectedNil(key_name(key))),
+                result => result,
+            };
+            self.parent.leave(result)
         } else {
-            panic!("MapDeserializer.next_value_seed was called before next_key_seed")
+            Err(de::Error::custom(
+                "MapDeserializer.next_value_seed was called before next_key_seed",
+            ))
         }
     }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.entries.len())
+    }
 }
 
 struct EnumDeserializer<'de> {
+    parent: Deserializer<'de>,
+    // The name of the enum, as serde knows it.
+    name: &'static str,
     variant: &'de Term,
+    // The names of the enum's variants, as serde knows them.
+    variants: &'static [&'static str],
     term: &'de Term,
+    // The name of the variant to use in place of `variant`.
+    catch_all: Option<&'de str>,
+    // The elements following the tag, if the variant is a flat tuple.  `term`
+    // is then the whole tuple.
+    flat: Option<&'de [Term]>,
 }
 
 impl<'de> EnumDeserializer<'de> {
-    fn new(variant: &'de Term, term: &'de Term) -> Self {
-        EnumDeserializer { variant, term }
+    fn new(
+        parent: Deserializer<'de>,
+        name: &'static str,
+        variant: &'de Term,
+        variants: &'static [&'static str],
+        term: &'de Term,
+    ) -> Self {
+        EnumDeserializer {
+            parent,
+            name,
+            variant,
+            variants,
+            term,
+            catch_all: None,
+            flat: None,
+        }
+    }
+
+    fn nested(&self) -> Result<Deserializer<'de>> {
+        let variant = self.variant;
+        self.parent.nested(self.term, || Segment::from_key(variant))
     }
 }
 
@@ -586,7 +1607,18 @@ impl<'de> EnumAccess<'de> for EnumDeserializer<'de> {
     where
         V: DeserializeSeed<'de>,
     {
-        let val = seed.deserialize(VariantNameDeserializer::from_term(self.variant))?;
+        let val = match self.catch_all {
+            Some(name) => {
+                let deserializer: de::value::StrDeserializer<Error> = name.into_deserializer();
+                seed.deserialize(deserializer)?
+            }
+            None => seed.deserialize(VariantNameDeserializer {
+                term: self.variant,
+                name: self.name,
+                case: self.parent.config.variant_case,
+                variants: self.variants,
+            })?,
+        };
         Ok((val, self))
     }
 }
@@ -608,37 +1640,96 @@ impl<'de> VariantAccess<'de> for EnumDeserializer<'de> {
     where
         T: DeserializeSeed<'de>,
     {
-        seed.deserialize(Deserializer::from_term(self.term))
+        let variant = self.variant;
+        let result = match self.flat {
+            Some([value]) => {
+                seed.deserialize(self.parent.nested(value, || Segment::from_key(variant))?)
+            }
+            Some(_) => Err(Error::MisSizedVariantTuple),
+            None => seed.deserialize(self.nested()?),
+        };
+        self.parent.leave(result)
     }
 
-    // Tuple variants are represented in JSON as `{ NAME: [DATA...] }` so
-    // deserialize the sequence of data here.
+    // Tuple variants are represented as `{name, {data...}}` so deserialize the
+    // inner tuple here, or the rest of a flat `{name, data...}` tuple.
     fn tuple_variant<V>(self, len: usize, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        let deserializer = Deserializer::from_term(self.term);
-        de::Deserializer::deserialize_tuple(deserializer, len, visitor)
+        let result = match self.flat {
+            Some(elements) if elements.len() == len => {
+                visitor.visit_seq(ListDeserializer::new(self.nested()?, elements))
+            }
+            Some(_) => Err(Error::WrongTupleLength),
+            None => de::Deserializer::deserialize_tuple(self.nested()?, len, visitor),
+        };
+        self.parent.leave(result)
     }
 
-    // Struct variants are represented in JSON as `{ NAME: { K: V, ... } }` so
-    // deserialize the inner map here.
-    fn struct_variant<V>(self, _fields: &'static [&'static str], visitor: V) -> Result<V::Value>
+    // Struct variants are represented as `{name, #{k => v, ...}}` so
+    // deserialize the inner map here, or the rest of a flat tuple.
+    fn struct_variant<V>(self, fields: &'static [&'static str], visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        let deserializer = Deserializer::from_term(self.term);
-        de::Deserializer::deserialize_map(deserializer, visitor)
+        let result = match self.flat {
+            // The fields are in order, which serde can deserialize as a seq.
+            Some(elements) if elements.len() == fields.len() => {
+                visitor.visit_seq(ListDeserializer::new(self.nested()?, elements))
+            }
+            Some(_) => Err(Error::WrongTupleLength),
+            None => de::Deserializer::deserialize_map(self.nested()?, visitor),
+        };
+        self.parent.leave(result)
     }
 }
 
 struct VariantNameDeserializer<'a> {
     term: &'a Term,
+    name: &'static str,
+    case: VariantCase,
+    variants: &'static [&'static str],
 }
 
-impl<'a> VariantNameDeserializer<'a> {
-    pub fn from_term(term: &'a Term) -> Self {
-        VariantNameDeserializer { term }
+// Converts a variant tag into the name of the rust variant it refers to.
+fn variant_name(term: &Term, name: &str, case: VariantCase, variants: &[&str]) -> Option<String> {
+    match term {
+        Term::Atom(atom) => Some(variant_from_tag(&atom.name, name, case, variants)),
+        // Some Elixir code tags tuples with a binary rather than an atom.
+        Term::Binary(binary) => str::from_utf8(&binary.bytes)
+            .ok()
+            .map(|tag| variant_from_tag(tag, name, case, variants)),
+        _ => None,
+    }
+}
+
+// Finds the variant that the serializer would have given `tag`.
+//
+// Converting the tag back with to_camel_case isn't reliable, since the
+// conversion is lossy: `HTTPError` is tagged `http_error`, and variants that
+// serde has already renamed might not be camel case at all.  So instead we
+// apply the serializer's conversion to each variant & compare.
+fn variant_from_tag(tag: &str, name: &str, case: VariantCase, variants: &[&str]) -> String {
+    // Results are tagged with ok & error, like in erlang.  Older versions
+    // tagged errors with err, so that's accepted too.
+    let result_variant = variants
+        .iter()
+        .find(|variant| match result_tag(name, variant) {
+            Some("error") => tag == "error" || tag == "err",
+            Some(expected) => expected == tag,
+            None => false,
+        });
+    if let Some(variant) = result_variant {
+        return variant.to_string();
+    }
+    match case {
+        VariantCase::SnakeCase => variants
+            .iter()
+            .find(|variant| variant.to_snake_case() == tag)
+            .map(|variant| variant.to_string())
+            .unwrap_or_else(|| tag.to_camel_case()),
+        VariantCase::AsIs => tag.to_string(),
     }
 }
 
@@ -649,9 +1740,12 @@ impl<'de, 'a: 'de> de::Deserializer<'de> for VariantNameDeserializer<'a> {
     where
         V: Visitor<'de>,
     {
-        match self.term {
-            Term::Atom(atom) => visitor.visit_string(atom.name.to_camel_case()),
-            _ => Err(Error::ExpectedAtom),
+        match variant_name(self.term, self.name, self.case, self.variants) {
+            Some(name) => visitor.visit_string(name),
+            None => match self.term {
+                Term::Binary(_) => Err(Error::Utf8DecodeError),
+                _ => Err(type_mismatch("atom", self.term)),
+            },
         }
     }
 
@@ -662,29 +1756,6 @@ impl<'de, 'a: 'de> de::Deserializer<'de> for VariantNameDeserializer<'a> {
     }
 }
 
-mod private {
-    // Some code I stole from serde.
-
-    /// Avoid having to restate the generic types on `MapDeserializer`. The
-    /// `Iterator::Item` contains enough information to figure out K and V.
-    pub trait Pair<'a> {
-        type First;
-        type Second;
-        fn split(self) -> &'a (Self::First, Self::Second);
-    }
-
-    impl<'a, A, B> Pair<'a> for &'a (A, B) {
-        type First = A;
-        type Second = B;
-        fn split(self) -> &'a (A, B) {
-            self
-        }
-    }
-
-    pub type First<'a, T> = <T as Pair<'a>>::First;
-    pub type Second<'a, T> = <T as Pair<'a>>::Second;
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -697,10 +1768,21 @@ mod tests {
     where
         T: DeserializeOwned,
     {
+        from_bytes(&encode(input)).expect("deserialize failed")
+    }
+
+    fn encode(term: Term) -> Vec<u8> {
         let mut cursor = io::Cursor::new(vec![]);
-        Term::encode(&input, &mut cursor).expect("encode failed");
+        term.encode(&mut cursor).expect("encode failed");
+        cursor.into_inner()
+    }
+
+    fn atom(name: &str) -> Term {
+        Term::Atom(eetf::Atom::from(name))
+    }
 
-        from_bytes(&cursor.into_inner()).expect("deserialize failed")
+    fn int(value: i32) -> Term {
+        Term::FixInteger(eetf::FixInteger::from(value))
     }
 
     #[test]
@@ -774,9 +1856,11 @@ mod tests {
     #[test]
     fn test_option() {
         let nil_result: Option<u8> = deserialize(Term::Atom(eetf::Atom::from("nil")));
+        let undefined_result: Option<u8> = deserialize(Term::Atom(eetf::Atom::from("undefined")));
         let some_result: Option<u8> = deserialize(Term::FixInteger(eetf::FixInteger::from(0)));
 
         assert_eq!(nil_result, None);
+        assert_eq!(undefined_result, None);
 
         assert_eq!(some_result, Some(0));
     }
@@ -794,6 +1878,33 @@ mod tests {
         assert_eq!(result, E::AnOption);
     }
 
+    #[test]
+    fn test_multi_word_variant_roundtrip() {
+        use crate::to_bytes;
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        enum E {
+            AnOption,
+            HTTPError(u16),
+        }
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        #[serde(rename_all = "snake_case")]
+        enum Renamed {
+            AnOption,
+        }
+
+        for value in vec![E::AnOption, E::HTTPError(404)] {
+            let bytes = to_bytes(&value).expect("serialize failed");
+            let result: E = from_bytes(&bytes).expect("deserialize failed");
+            assert_eq!(result, value);
+        }
+
+        let bytes = to_bytes(&Renamed::AnOption).expect("serialize failed");
+        let result: Renamed = from_bytes(&bytes).expect("deserialize failed");
+        assert_eq!(result, Renamed::AnOption);
+    }
+
     #[test]
     fn test_newtype_variant() {
         // Not 100% sure if this is a tuple variant or a newtype variant.
@@ -830,6 +1941,1252 @@ mod tests {
 
         assert_eq!(result, Testing::Ok(1, 2));
     }
-    // TODO: test actual maps, as well as structs.  Suspect they're broken.
-    // some quickcheck based roundtrip tests would also be great.
+
+    #[test]
+    fn test_invalid_type_error() {
+        let bytes = encode(Term::Binary(eetf::Binary::from("nope".as_bytes())));
+
+        let result: Result<crate::FlexibleTime> = from_bytes(&bytes);
+
+        match result {
+            Err(Error::InvalidType { found, .. }) => assert_eq!(found, "byte array"),
+            other => panic!("Expected an InvalidType error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_struct_with_mixed_fields() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        enum LogLevel {
+            Debug,
+            Info,
+        }
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Config {
+            name: String,
+            timeout: i64,
+            enabled: bool,
+            description: Option<String>,
+            max_connections: Option<u16>,
+            log_level: LogLevel,
+        }
+
+        let result: Config = deserialize(Term::Map(eetf::Map::from(vec![
+            (
+                Term::Atom(eetf::Atom::from("name")),
+                Term::Binary(eetf::Binary::from("server".as_bytes())),
+            ),
+            (
+                Term::Atom(eetf::Atom::from("timeout")),
+                Term::BigInteger(eetf::BigInteger::from(5_000_000_000i64)),
+            ),
+            (
+                Term::Atom(eetf::Atom::from("enabled")),
+                Term::Atom(eetf::Atom::from("true")),
+            ),
+            (
+                Term::Atom(eetf::Atom::from("description")),
+                Term::Atom(eetf::Atom::from("nil")),
+            ),
+            (
+                Term::Atom(eetf::Atom::from("max_connections")),
+                Term::FixInteger(eetf::FixInteger::from(100)),
+            ),
+            (
+                Term::Atom(eetf::Atom::from("log_level")),
+                Term::Atom(eetf::Atom::from("info")),
+            ),
+        ])));
+
+        assert_eq!(
+            result,
+            Config {
+                name: "server".to_string(),
+                timeout: 5_000_000_000,
+                enabled: true,
+                description: None,
+                max_connections: Some(100),
+                log_level: LogLevel::Info,
+            }
+        );
+    }
+
+    #[test]
+    fn test_nested_generics() {
+        use std::collections::HashMap;
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Item {
+            id: u8,
+        }
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        enum Colour {
+            Red,
+            Green,
+        }
+
+        let item = |id: i32| {
+            Term::Map(eetf::Map::from(vec![(
+                Term::Atom(eetf::Atom::from("id")),
+                Term::FixInteger(eetf::FixInteger::from(id)),
+            )]))
+        };
+
+        let items: Option<Vec<Item>> =
+            deserialize(Term::List(eetf::List::from(vec![item(1), item(2)])));
+        assert_eq!(items, Some(vec![Item { id: 1 }, Item { id: 2 }]));
+
+        let no_items: Option<Vec<Item>> = deserialize(Term::Atom(eetf::Atom::from("nil")));
+        assert_eq!(no_items, None);
+
+        let maybe_ints: Vec<Option<i32>> = deserialize(Term::List(eetf::List::from(vec![
+            Term::FixInteger(eetf::FixInteger::from(1)),
+            Term::Atom(eetf::Atom::from("nil")),
+            Term::FixInteger(eetf::FixInteger::from(3)),
+        ])));
+        assert_eq!(maybe_ints, vec![Some(1), None, Some(3)]);
+
+        let colours: HashMap<String, Vec<Colour>> = deserialize(Term::Map(eetf::Map::from(vec![
+            (
+                Term::Binary(eetf::Binary::from("warm".as_bytes())),
+                Term::List(eetf::List::from(vec![Term::Atom(eetf::Atom::from("red"))])),
+            ),
+            (
+                Term::Binary(eetf::Binary::from("cool".as_bytes())),
+                Term::List(eetf::List::from(vec![
+                    Term::Atom(eetf::Atom::from("green")),
+                    Term::Atom(eetf::Atom::from("red")),
+                ])),
+            ),
+        ])));
+        let mut expected = HashMap::new();
+        expected.insert("warm".to_string(), vec![Colour::Red]);
+        expected.insert("cool".to_string(), vec![Colour::Green, Colour::Red]);
+        assert_eq!(colours, expected);
+    }
+
+    #[test]
+    fn test_from_bytes_with_warnings_narrows_integers() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct TestStruct {
+            small: u8,
+            exact: u32,
+        }
+
+        let input = Term::Map(eetf::Map::from(vec![
+            (
+                Term::Atom(eetf::Atom::from("small")),
+                Term::BigInteger(eetf::BigInteger::from(70000)),
+            ),
+            (
+                Term::Atom(eetf::Atom::from("exact")),
+                Term::FixInteger(eetf::FixInteger::from(12)),
+            ),
+        ]));
+        let bytes = encode(input);
+
+        assert_eq!(
+            from_bytes::<TestStruct>(&bytes),
+            Err(Error::IntegerConvertError)
+        );
+
+        let mut warnings = vec![];
+        let result: TestStruct =
+            from_bytes_with_warnings(&bytes, &mut warnings).expect("deserialize failed");
+
+        assert_eq!(
+            result,
+            TestStruct {
+                small: 255,
+                exact: 12
+            }
+        );
+        assert_eq!(
+            warnings,
+            vec![Warning::IntegerNarrowed {
+                original: "70000".to_string(),
+                narrowed: "255".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_untagged_enum_by_arity() {
+        // serde's untagged enums go through deserialize_any, so variants that
+        // only differ in tuple arity can be told apart.
+        #[derive(Deserialize, Debug, PartialEq)]
+        #[serde(untagged)]
+        enum Shape {
+            Pair(i32, i32),
+            Triple(i32, i32, i32),
+        }
+
+        let result: Shape = deserialize(Term::Tuple(eetf::Tuple::from(vec![int(1), int(2)])));
+        assert_eq!(result, Shape::Pair(1, 2));
+
+        let result: Shape =
+            deserialize(Term::Tuple(eetf::Tuple::from(vec![int(1), int(2), int(3)])));
+        assert_eq!(result, Shape::Triple(1, 2, 3));
+    }
+
+    #[test]
+    fn test_borrowed_str_and_bytes() {
+        use serde::Deserialize;
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Borrowed<'a> {
+            text: &'a str,
+            data: &'a [u8],
+        }
+
+        let term = Term::Map(eetf::Map::from(vec![
+            (
+                Term::Atom(eetf::Atom::from("text")),
+                Term::Binary(eetf::Binary::from("hello".as_bytes())),
+            ),
+            (
+                Term::Atom(eetf::Atom::from("data")),
+                Term::Binary(eetf::Binary::from(&[1, 2, 3][..])),
+            ),
+        ]));
+
+        let result =
+            Borrowed::deserialize(Deserializer::from_term(&term)).expect("deserialize failed");
+
+        assert_eq!(
+            result,
+            Borrowed {
+                text: "hello",
+                data: &[1, 2, 3],
+            }
+        );
+    }
+
+    #[test]
+    fn test_explain_mismatch() {
+        #[derive(Deserialize, Debug)]
+        struct Config {
+            timeout: u32,
+            retries: u8,
+        }
+
+        #[derive(Deserialize, Debug)]
+        struct Outer {
+            config: Config,
+        }
+
+        let timeout = Term::Binary(eetf::Binary::from("5s".as_bytes()));
+        let bytes = encode(Term::Map(eetf::Map::from(vec![(
+            atom("config"),
+            Term::Map(eetf::Map::from(vec![
+                (atom("timeout"), timeout.clone()),
+                (atom("retries"), int(1)),
+            ])),
+        )])));
+        assert_eq!(
+            explain_mismatch::<Outer>(&bytes),
+            format!(
+                "at `config.timeout`: Expected integer, got binary: {}",
+                timeout
+            )
+        );
+
+        let bytes = encode(Term::Map(eetf::Map::from(vec![(
+            atom("config"),
+            Term::Map(eetf::Map::from(vec![(atom("timeout"), int(5))])),
+        )])));
+        assert_eq!(
+            explain_mismatch::<Outer>(&bytes),
+            "at `config`: missing field `retries` (found map)"
+        );
+
+        let bytes = encode(Term::List(eetf::List::from(vec![int(1), atom("two")])));
+        assert_eq!(
+            explain_mismatch::<Vec<u8>>(&bytes),
+            format!("at `[1]`: Expected integer, got atom: {}", atom("two"))
+        );
+
+        let bytes = encode(atom("nope"));
+        assert_eq!(
+            explain_mismatch::<Vec<u8>>(&bytes),
+            format!(
+                "at the top level: Expected list, got atom: {}",
+                atom("nope")
+            )
+        );
+
+        let bytes = encode(Term::List(eetf::List::from(vec![int(1)])));
+        assert_eq!(explain_mismatch::<Vec<u8>>(&bytes), "no mismatch");
+    }
+
+    #[test]
+    fn test_type_mismatch_truncates_long_terms() {
+        let list = Term::List(eetf::List::from((0..100).map(int).collect::<Vec<_>>()));
+
+        match from_bytes::<u8>(&encode(list.clone())) {
+            Err(Error::TypeMismatch { term, .. }) => {
+                assert!(term.ends_with("..."));
+                assert_eq!(term.chars().count(), MAX_INSPECT_LEN + 3);
+                assert!(list.to_string().starts_with(&term[..term.len() - 3]));
+            }
+            other => panic!("Expected a TypeMismatch, got {:?}", other),
+        }
+
+        let short = Term::List(eetf::List::from(vec![int(1), int(2)]));
+        assert_eq!(inspect(&short), short.to_string());
+    }
+
+    #[test]
+    fn test_from_bytes_by_field_order() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Point(i32, i32);
+
+        let input = Term::Map(eetf::Map::from(vec![
+            (
+                Term::Binary(eetf::Binary::from("y".as_bytes())),
+                Term::FixInteger(eetf::FixInteger::from(2)),
+            ),
+            (
+                Term::Atom(eetf::Atom::from("label")),
+                Term::Binary(eetf::Binary::from("origin".as_bytes())),
+            ),
+            (
+                Term::Atom(eetf::Atom::from("x")),
+                Term::FixInteger(eetf::FixInteger::from(1)),
+            ),
+        ]));
+        let bytes = encode(input);
+
+        let result: Point =
+            from_bytes_by_field_order(&bytes, &["x", "y"]).expect("deserialize failed");
+        assert_eq!(result, Point(1, 2));
+
+        let result: Result<Point> = from_bytes_by_field_order(&bytes, &["x", "z"]);
+        assert_eq!(result, Err(Error::Message("missing field `z`".to_string())));
+    }
+
+    #[test]
+    fn test_floats_as_integers() {
+        let float = |value: f64| encode(Term::Float(eetf::Float { value }));
+        let config = DeserializerConfig {
+            float_to_integer: FloatToInteger::AcceptWhole,
+            ..DeserializerConfig::default()
+        };
+
+        assert_eq!(
+            from_bytes::<i32>(&float(1.0)),
+            Err(Error::TypeMismatch {
+                expected: "integer",
+                got: "float",
+                term: Term::Float(eetf::Float { value: 1.0 }).to_string()
+            })
+        );
+        assert_eq!(from_bytes_with_config::<i32>(&float(1.0), &config), Ok(1));
+        assert_eq!(from_bytes_with_config::<i64>(&float(-3.0), &config), Ok(-3));
+        assert_eq!(
+            from_bytes_with_config::<i32>(&float(1.5), &config),
+            Err(Error::InvalidValue {
+                expected: "a float with no fractional part".to_string(),
+                found: "floating point `1.5`".to_string(),
+            })
+        );
+        assert_eq!(
+            from_bytes_with_config::<u8>(&float(300.0), &config),
+            Err(Error::IntegerConvertError)
+        );
+    }
+
+    #[test]
+    fn test_enum_from_list() {
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        enum Message {
+            Text(String),
+        }
+
+        let result: Message = deserialize(Term::List(eetf::List::from(vec![
+            Term::Atom(eetf::Atom::from("text")),
+            Term::Binary(eetf::Binary::from("hi".as_bytes())),
+        ])));
+        assert_eq!(result, Message::Text("hi".to_string()));
+
+        // Serializing again gives the usual tuple, which reads back the same.
+        let bytes = crate::to_bytes(&result).expect("serialize failed");
+        let roundtripped: Message = from_bytes(&bytes).expect("deserialize failed");
+        assert_eq!(roundtripped, result);
+    }
+
+    #[test]
+    fn test_from_term() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Point(u8, u8);
+
+        let result: Point = from_term(Term::Tuple(eetf::Tuple::from(vec![
+            Term::FixInteger(eetf::FixInteger::from(1)),
+            Term::FixInteger(eetf::FixInteger::from(2)),
+        ])))
+        .expect("deserialize failed");
+
+        assert_eq!(result, Point(1, 2));
+    }
+
+    #[test]
+    fn test_catch_all_variant() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        enum Event {
+            Click(u8),
+            Other(crate::EetfValue),
+        }
+
+        let scroll = Term::Tuple(eetf::Tuple::from(vec![
+            Term::Atom(eetf::Atom::from("scroll")),
+            Term::FixInteger(eetf::FixInteger::from(5)),
+        ]));
+        let click = Term::Tuple(eetf::Tuple::from(vec![
+            Term::Atom(eetf::Atom::from("click")),
+            Term::FixInteger(eetf::FixInteger::from(1)),
+        ]));
+        let config = DeserializerConfig {
+            catch_all_variant: Some("Other".to_string()),
+            ..DeserializerConfig::default()
+        };
+        let from_term =
+            |term: &Term| from_bytes_with_config::<Event>(&encode(term.clone()), &config);
+
+        assert_eq!(from_term(&click), Ok(Event::Click(1)));
+        assert_eq!(
+            from_term(&scroll),
+            Ok(Event::Other(crate::EetfValue(scroll.clone())))
+        );
+
+        assert!(from_bytes::<Event>(&encode(scroll)).is_err());
+    }
+
+    #[test]
+    fn test_large_integer_roundtrips() {
+        let bytes = crate::to_bytes(&u64::max_value()).expect("serialize failed");
+        assert_eq!(from_bytes::<u64>(&bytes), Ok(u64::max_value()));
+        assert_eq!(from_bytes::<i64>(&bytes), Err(Error::IntegerConvertError));
+
+        let bytes = crate::to_bytes(&u128::max_value()).expect("serialize failed");
+        assert_eq!(from_bytes::<u128>(&bytes), Ok(u128::max_value()));
+
+        for value in &[i128::min_value(), -1, i128::max_value()] {
+            let bytes = crate::to_bytes(value).expect("serialize failed");
+            assert_eq!(from_bytes::<i128>(&bytes), Ok(*value));
+        }
+
+        let bytes = crate::to_bytes(&-1).expect("serialize failed");
+        assert_eq!(from_bytes::<u128>(&bytes), Err(Error::IntegerConvertError));
+    }
+
+    #[test]
+    fn test_out_of_range_integers() {
+        let minus_one = encode(Term::FixInteger(eetf::FixInteger::from(-1)));
+        let too_big = encode(Term::FixInteger(eetf::FixInteger::from(256)));
+        let big_minus_one = encode(Term::BigInteger(eetf::BigInteger::from(-1)));
+
+        assert_eq!(
+            from_bytes::<u8>(&minus_one),
+            Err(Error::IntegerConvertError)
+        );
+        assert_eq!(from_bytes::<u8>(&too_big), Err(Error::IntegerConvertError));
+        assert_eq!(from_bytes::<i8>(&too_big), Err(Error::IntegerConvertError));
+        assert_eq!(from_bytes::<u16>(&too_big), Ok(256));
+        assert_eq!(
+            from_bytes::<u32>(&big_minus_one),
+            Err(Error::IntegerConvertError)
+        );
+        assert_eq!(
+            from_bytes::<u64>(&big_minus_one),
+            Err(Error::IntegerConvertError)
+        );
+        assert_eq!(from_bytes::<i16>(&big_minus_one), Ok(-1));
+    }
+
+    #[test]
+    fn test_empty_binaries() {
+        let empty = || Term::Binary(eetf::Binary::from(&[][..]));
+
+        let result: String = deserialize(empty());
+        assert_eq!(result, "");
+
+        let result: Vec<u8> = deserialize(empty());
+        assert_eq!(result, Vec::<u8>::new());
+
+        let result: Vec<u8> = deserialize(Term::Binary(eetf::Binary::from(&[1, 2][..])));
+        assert_eq!(result, vec![1, 2]);
+
+        let result: Vec<u8> = deserialize(Term::List(eetf::List::from(vec![])));
+        assert_eq!(result, Vec::<u8>::new());
+    }
+
+    #[test]
+    fn test_for_each_term() {
+        let mut bytes: Vec<u8> = (0..100).flat_map(|i| encode(int(i))).collect();
+        bytes.extend(encode(atom("not_a_number")));
+
+        let mut results: Vec<Result<u8>> = vec![];
+        for_each_term(io::Cursor::new(bytes), |result| results.push(result));
+
+        assert_eq!(results.len(), 101);
+        for (i, result) in results[..100].iter().enumerate() {
+            assert_eq!(*result, Ok(i as u8));
+        }
+        assert!(results[100].is_err());
+    }
+
+    #[test]
+    fn test_for_each_term_stops_on_decode_error() {
+        let mut bytes = encode(int(1));
+        // A version byte followed by an unknown tag, then a valid term.
+        bytes.extend_from_slice(&[131, 0, 131, 97, 2]);
+
+        let mut results: Vec<Result<u8>> = vec![];
+        for_each_term(io::Cursor::new(bytes), |result| results.push(result));
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0], Ok(1));
+        match results[1] {
+            Err(Error::DecodeError(_)) => (),
+            ref other => panic!("Expected a DecodeError, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_from_bytes_with_trailing() {
+        let mut bytes = encode(int(300));
+        bytes.extend(encode(atom("next")));
+
+        let (first, used) = from_bytes_with_trailing::<u16>(&bytes).expect("deserialize failed");
+        assert_eq!((first, used), (300, 6));
+        let (second, used) =
+            from_bytes_with_trailing::<crate::Atom>(&bytes[used..]).expect("deserialize failed");
+        assert_eq!(second, crate::Atom::from("next"));
+        assert_eq!(used, bytes.len() - 6);
+    }
+
+    #[test]
+    fn test_from_bytes_no_version() {
+        // A small integer, without the version byte before it.
+        assert_eq!(from_bytes_no_version::<u8>(&[97, 5]), Ok(5));
+        assert_eq!(from_bytes_no_version::<u8>(&[]), Err(Error::UnexpectedEof));
+        // With the version byte, 131 is taken to be the tag of the term.
+        assert!(from_bytes_no_version::<u8>(&[131, 97, 5]).is_err());
+    }
+
+    #[test]
+    fn test_term_stream() {
+        let bytes: Vec<u8> = (0..3).flat_map(|i| encode(int(i))).collect();
+
+        let results: Vec<Result<u8>> = TermStream::new(bytes.as_slice()).collect();
+        assert_eq!(results, vec![Ok(0), Ok(1), Ok(2)]);
+
+        // Running out of input part way through a term is an error.
+        let mut stream = TermStream::<_, u8>::new(&bytes[..bytes.len() - 1]);
+        assert_eq!(stream.next(), Some(Ok(0)));
+        assert_eq!(stream.next(), Some(Ok(1)));
+        assert_eq!(stream.next(), Some(Err(Error::UnexpectedEof)));
+        assert_eq!(stream.next(), None);
+    }
+
+    #[test]
+    fn test_nil_struct_fields() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Optional {
+            count: Option<u8>,
+        }
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Required {
+            count: u8,
+        }
+
+        let bytes = encode(Term::Map(eetf::Map::from(vec![(
+            atom("count"),
+            atom("nil"),
+        )])));
+
+        assert_eq!(from_bytes(&bytes), Ok(Optional { count: None }));
+        assert_eq!(
+            from_bytes::<Required>(&bytes),
+            Err(Error::UnexpectedNil("count".to_string()))
+        );
+
+        // Any atom that would be None is reported the same way.
+        let bytes = encode(Term::Map(eetf::Map::from(vec![(
+            atom("count"),
+            atom("undefined"),
+        )])));
+        assert_eq!(
+            from_bytes::<Required>(&bytes),
+            Err(Error::UnexpectedNil("count".to_string()))
+        );
+
+        let config = DeserializerConfig {
+            none_atom: Some("null".to_string()),
+            ..DeserializerConfig::default()
+        };
+        let bytes = encode(Term::Map(eetf::Map::from(vec![(
+            atom("count"),
+            atom("null"),
+        )])));
+        assert_eq!(
+            from_bytes_with_config::<Required>(&bytes, &config),
+            Err(Error::UnexpectedNil("count".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_nil_maps() {
+        use std::collections::HashMap;
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Counts {
+            counts: HashMap<String, u8>,
+        }
+
+        let bytes = encode(Term::Map(eetf::Map::from(vec![(
+            atom("counts"),
+            atom("nil"),
+        )])));
+        assert_eq!(
+            from_bytes::<Counts>(&bytes),
+            Err(Error::UnexpectedNil("counts".to_string()))
+        );
+
+        let config = DeserializerConfig {
+            accept_nil_maps: true,
+            ..DeserializerConfig::default()
+        };
+        assert_eq!(
+            from_bytes_with_config(&bytes, &config),
+            Ok(Counts {
+                counts: HashMap::new()
+            })
+        );
+    }
+
+    #[test]
+    fn test_structs_with_binary_keys() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Point {
+            x: i32,
+            y: i32,
+        }
+
+        let point = |x_key: Term| {
+            encode(Term::Map(eetf::Map::from(vec![
+                (x_key, int(1)),
+                (atom("y"), int(2)),
+            ])))
+        };
+
+        let bytes = point(Term::Binary(eetf::Binary::from("x".as_bytes())));
+        assert_eq!(from_bytes(&bytes), Ok(Point { x: 1, y: 2 }));
+
+        let bytes = point(Term::Binary(eetf::Binary::from(&[0xff][..])));
+        assert_eq!(from_bytes::<Point>(&bytes), Err(Error::Utf8DecodeError));
+    }
+
+    #[test]
+    fn test_maps_with_integer_and_tuple_keys() {
+        use std::collections::HashMap;
+
+        let bytes = encode(Term::Map(eetf::Map::from(vec![
+            (int(1), Term::Binary(eetf::Binary::from("one".as_bytes()))),
+            (
+                int(-20),
+                Term::Binary(eetf::Binary::from("minus twenty".as_bytes())),
+            ),
+        ])));
+        let result: HashMap<i32, String> = from_bytes(&bytes).expect("deserialize failed");
+        let mut expected = HashMap::new();
+        expected.insert(1, "one".to_string());
+        expected.insert(-20, "minus twenty".to_string());
+        assert_eq!(result, expected);
+
+        let bytes = encode(Term::Map(eetf::Map::from(vec![
+            (Term::Tuple(eetf::Tuple::from(vec![int(0), int(1)])), int(5)),
+            (Term::Tuple(eetf::Tuple::from(vec![int(2), int(3)])), int(7)),
+        ])));
+        let result: HashMap<(i32, i32), u8> = from_bytes(&bytes).expect("deserialize failed");
+        let mut expected = HashMap::new();
+        expected.insert((0, 1), 5);
+        expected.insert((2, 3), 7);
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_struct_fields_by_index() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Point {
+            x: i32,
+            y: i32,
+        }
+
+        let bytes = encode(Term::Map(eetf::Map::from(vec![
+            (int(1), int(2)),
+            (int(0), int(1)),
+        ])));
+
+        assert_eq!(from_bytes(&bytes), Ok(Point { x: 1, y: 2 }));
+    }
+
+    #[test]
+    fn test_any_booleans() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        #[serde(untagged)]
+        enum Flag {
+            Bool(bool),
+            Text(String),
+        }
+
+        let result: Flag = deserialize(atom("true"));
+        assert_eq!(result, Flag::Bool(true));
+        let result: Flag = deserialize(atom("false"));
+        assert_eq!(result, Flag::Bool(false));
+        let result: Flag = deserialize(atom("maybe"));
+        assert_eq!(result, Flag::Text("maybe".to_string()));
+
+        // Asking for a string still gets the name of the atom.
+        let result: String = deserialize(atom("true"));
+        assert_eq!(result, "true");
+    }
+
+    #[test]
+    fn test_binary_as_string() {
+        use std::fmt;
+
+        // Records what deserialize_any visited.
+        #[derive(Debug, PartialEq)]
+        enum Value {
+            Text(String),
+            Bytes(Vec<u8>),
+        }
+
+        impl<'de> de::Deserialize<'de> for Value {
+            fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+            where
+                D: de::Deserializer<'de>,
+            {
+                deserializer.deserialize_any(ValueVisitor)
+            }
+        }
+
+        struct ValueVisitor;
+
+        impl<'de> Visitor<'de> for ValueVisitor {
+            type Value = Value;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a string or bytes")
+            }
+
+            fn visit_str<E>(self, v: &str) -> std::result::Result<Value, E>
+            where
+                E: de::Error,
+            {
+                Ok(Value::Text(v.to_string()))
+            }
+
+            fn visit_bytes<E>(self, v: &[u8]) -> std::result::Result<Value, E>
+            where
+                E: de::Error,
+            {
+                Ok(Value::Bytes(v.to_vec()))
+            }
+        }
+
+        let binary = |bytes: &[u8]| encode(Term::Binary(eetf::Binary::from(bytes)));
+        let config = DeserializerConfig {
+            binary_as: BinaryInterpretation::StringIfUtf8,
+            ..DeserializerConfig::default()
+        };
+
+        assert_eq!(
+            from_bytes_with_config(&binary(b"hello"), &config),
+            Ok(Value::Text("hello".to_string()))
+        );
+        assert_eq!(
+            from_bytes_with_config(&binary(&[0xff]), &config),
+            Ok(Value::Bytes(vec![0xff]))
+        );
+        assert_eq!(
+            from_bytes(&binary(b"hello")),
+            Ok(Value::Bytes(b"hello".to_vec()))
+        );
+    }
+
+    #[test]
+    fn test_struct_from_tuple() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Point {
+            x: i32,
+            y: i32,
+        }
+
+        let result: Point = deserialize(Term::Tuple(eetf::Tuple::from(vec![int(1), int(2)])));
+        assert_eq!(result, Point { x: 1, y: 2 });
+
+        let bytes = encode(Term::Tuple(eetf::Tuple::from(vec![int(1), int(2), int(3)])));
+        assert_eq!(from_bytes::<Point>(&bytes), Err(Error::WrongTupleLength));
+    }
+
+    #[test]
+    fn test_elixir_atoms() {
+        // Atoms written as `:ok` in Elixir are sent without the colon.
+        #[derive(Deserialize, Debug, PartialEq)]
+        enum Status {
+            Ok,
+            NotFound,
+        }
+
+        let result: Status = deserialize(atom("ok"));
+        assert_eq!(result, Status::Ok);
+        let result: Status = deserialize(atom("not_found"));
+        assert_eq!(result, Status::NotFound);
+        let result: bool = deserialize(atom("true"));
+        assert_eq!(result, true);
+        let result: Option<u8> = deserialize(atom("nil"));
+        assert_eq!(result, None);
+    }
+
+    #[test]
+    fn test_chars() {
+        let result: char = deserialize(Term::FixInteger(eetf::FixInteger::from(65)));
+        assert_eq!(result, 'A');
+        let result: char = deserialize(Term::FixInteger(eetf::FixInteger::from(0x1F600)));
+        assert_eq!(result, '😀');
+        let result: char = deserialize(Term::Binary(eetf::Binary::from("é".as_bytes())));
+        assert_eq!(result, 'é');
+
+        for term in vec![
+            Term::FixInteger(eetf::FixInteger::from(-1)),
+            Term::FixInteger(eetf::FixInteger::from(0xD800)),
+            Term::Binary(eetf::Binary::from("ab".as_bytes())),
+        ] {
+            assert_eq!(from_bytes::<char>(&encode(term)), Err(Error::ExpectedChar));
+        }
+    }
+
+    #[test]
+    fn test_charlist_strings() {
+        use crate::{to_bytes_with_config, SerializerConfig, StringFormat};
+
+        let config = SerializerConfig {
+            string_format: StringFormat::CharList,
+            ..SerializerConfig::default()
+        };
+        let bytes = to_bytes_with_config("héllo wörld 😀", &config).expect("serialize failed");
+        let result: String = from_bytes(&bytes).expect("deserialize failed");
+        assert_eq!(result, "héllo wörld 😀");
+
+        let result: String = deserialize(Term::List(eetf::List::from(vec![])));
+        assert_eq!(result, "");
+
+        for element in vec![
+            Term::FixInteger(eetf::FixInteger::from(-1)),
+            Term::FixInteger(eetf::FixInteger::from(0xD800)),
+            Term::Atom(eetf::Atom::from("a")),
+        ] {
+            let bytes = encode(Term::List(eetf::List::from(vec![element])));
+            assert_eq!(from_bytes::<String>(&bytes), Err(Error::ExpectedBinary));
+        }
+    }
+
+    #[test]
+    fn test_unit_variant_atoms() {
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        enum Switch {
+            On,
+            Off,
+        }
+        #[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Copy)]
+        enum Protocol {
+            Http,
+            Http2,
+            V1Beta,
+            ReadOnly,
+            Level10Access,
+        }
+
+        let result: Switch = deserialize(atom("on"));
+        assert_eq!(result, Switch::On);
+        let result: Switch = deserialize(atom("off"));
+        assert_eq!(result, Switch::Off);
+
+        for (variant, name) in &[
+            (Protocol::Http, "http"),
+            (Protocol::Http2, "http2"),
+            (Protocol::V1Beta, "v1_beta"),
+            (Protocol::ReadOnly, "read_only"),
+            (Protocol::Level10Access, "level10_access"),
+        ] {
+            let bytes = crate::to_bytes(variant).expect("serialize failed");
+            assert_eq!(
+                Term::decode(io::Cursor::new(&bytes)).expect("decode failed"),
+                atom(name)
+            );
+            assert_eq!(from_bytes::<Protocol>(&bytes), Ok(*variant));
+        }
+    }
+
+    #[test]
+    fn test_enum_roundtrip() {
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        enum ErlResult {
+            Ok(String),
+            Error(String, u8),
+        }
+
+        for value in vec![
+            ErlResult::Ok("test".to_string()),
+            ErlResult::Error("failed".to_string(), 2),
+        ] {
+            let bytes = crate::to_bytes(&value).expect("serialize failed");
+            assert_eq!(from_bytes(&bytes), Ok(value));
+        }
+    }
+
+    #[test]
+    fn test_depth_limit() {
+        let mut term = int(1);
+        for _ in 0..10 {
+            term = Term::List(eetf::List::from(vec![term]));
+        }
+        let bytes = encode(term);
+
+        type Nested = Vec<Vec<Vec<Vec<Vec<Vec<Vec<Vec<Vec<Vec<u8>>>>>>>>>>;
+        assert!(from_bytes_with_limit::<Nested>(&bytes, 10).is_ok());
+        assert_eq!(
+            from_bytes_with_limit::<Nested>(&bytes, 9),
+            Err(Error::DepthLimitExceeded)
+        );
+    }
+
+    #[test]
+    fn test_ignores_unknown_compound_fields() {
+        use serde::de::IgnoredAny;
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Summary {
+            id: u8,
+        }
+
+        let nested = Term::Map(eetf::Map::from(vec![(
+            atom("items"),
+            Term::List(eetf::List::from(vec![
+                Term::Tuple(eetf::Tuple::from(vec![atom("a"), int(1)])),
+                Term::Map(eetf::Map::from(vec![(int(2), atom("b"))])),
+            ])),
+        )]));
+
+        let result: Summary = deserialize(Term::Map(eetf::Map::from(vec![
+            (atom("details"), nested.clone()),
+            (atom("id"), int(7)),
+            (
+                atom("history"),
+                Term::List(eetf::List::from(vec![nested.clone(), nested.clone()])),
+            ),
+        ])));
+        assert_eq!(result, Summary { id: 7 });
+
+        let _: IgnoredAny = deserialize(nested);
+    }
+
+    #[test]
+    fn test_reader_size_limit() {
+        let bytes = encode(Term::Binary(eetf::Binary::from(&[0; 100][..])));
+
+        let result: Vec<u8> = from_reader_with_limit(bytes.as_slice(), bytes.len() as u64)
+            .expect("deserialize failed");
+        assert_eq!(result, vec![0; 100]);
+        assert_eq!(
+            from_reader_with_limit::<_, Vec<u8>>(bytes.as_slice(), bytes.len() as u64 - 1),
+            Err(Error::InputTooLarge)
+        );
+    }
+
+    #[test]
+    fn test_max_atoms() {
+        let bytes = encode(Term::Tuple(eetf::Tuple::from(vec![
+            atom("ok"),
+            Term::List(eetf::List::from(vec![atom("a"), atom("b"), atom("a")])),
+        ])));
+
+        let config = |max_atoms| DeserializerConfig {
+            max_atoms: Some(max_atoms),
+            ..DeserializerConfig::default()
+        };
+        type Message = (crate::Atom, Vec<crate::Atom>);
+
+        assert!(from_bytes_with_config::<Message>(&bytes, &config(4)).is_ok());
+        assert_eq!(
+            from_bytes_with_config::<Message>(&bytes, &config(3)),
+            Err(Error::TooManyAtoms)
+        );
+
+        // The limit applies however the term is deserialized.
+        let mut warnings = vec![];
+        assert_eq!(
+            from_bytes_with_warnings_with_config::<Message>(&bytes, &mut warnings, &config(3)),
+            Err(Error::TooManyAtoms)
+        );
+        assert_eq!(
+            explain_mismatch_with_config::<Message>(&bytes, &config(3)),
+            format!("could not decode term: {}", Error::TooManyAtoms)
+        );
+        let bytes = encode(Term::Map(eetf::Map::from(vec![(atom("x"), atom("a"))])));
+        assert_eq!(
+            from_bytes_by_field_order_with_config::<(crate::Atom,)>(&bytes, &["x"], &config(1)),
+            Err(Error::TooManyAtoms)
+        );
+    }
+
+    #[test]
+    fn test_proplists() {
+        use std::collections::HashMap;
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Person {
+            name: String,
+            age: u8,
+        }
+
+        let age = Term::Tuple(eetf::Tuple::from(vec![
+            Term::Binary(eetf::Binary::from("age".as_bytes())),
+            int(30),
+        ]));
+        let proplist = Term::List(eetf::List::from(vec![
+            Term::Tuple(eetf::Tuple::from(vec![
+                atom("name"),
+                Term::Binary(eetf::Binary::from("x".as_bytes())),
+            ])),
+            age.clone(),
+        ]));
+        let bytes = encode(proplist.clone());
+
+        assert_eq!(
+            from_bytes::<Person>(&bytes),
+            Err(Error::TypeMismatch {
+                expected: "map",
+                got: "list",
+                term: proplist.to_string()
+            })
+        );
+
+        let config = DeserializerConfig {
+            accept_proplists: true,
+            ..DeserializerConfig::default()
+        };
+        assert_eq!(
+            from_bytes_with_config::<Person>(&bytes, &config),
+            Ok(Person {
+                name: "x".to_string(),
+                age: 30
+            })
+        );
+
+        let bytes = encode(Term::List(eetf::List::from(vec![age])));
+        let map: HashMap<String, u8> =
+            from_bytes_with_config(&bytes, &config).expect("deserialize failed");
+        assert_eq!(map["age"], 30);
+
+        // Lists of anything else still aren't maps.
+        let names = Term::List(eetf::List::from(vec![atom("name")]));
+        assert_eq!(
+            from_bytes_with_config::<Person>(&encode(names.clone()), &config),
+            Err(Error::TypeMismatch {
+                expected: "map",
+                got: "list",
+                term: names.to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_identifiers_in_any() {
+        struct Encoded(Vec<u8>);
+
+        impl<'de> de::Deserialize<'de> for Encoded {
+            fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+            where
+                D: de::Deserializer<'de>,
+            {
+                deserializer.deserialize_any(EncodedVisitor)
+            }
+        }
+
+        struct EncodedVisitor;
+
+        impl<'de> Visitor<'de> for EncodedVisitor {
+            type Value = Encoded;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("an encoded term")
+            }
+
+            fn visit_byte_buf<E>(self, v: Vec<u8>) -> std::result::Result<Encoded, E>
+            where
+                E: de::Error,
+            {
+                Ok(Encoded(v))
+            }
+        }
+
+        let node = eetf::Atom::from("node@localhost");
+        for term in vec![
+            Term::Reference(eetf::Reference {
+                node: node.clone(),
+                id: vec![1, 2, 3],
+                creation: 1,
+            }),
+            Term::Port(eetf::Port {
+                node: node.clone(),
+                id: 7,
+                creation: 1,
+            }),
+            Term::BitBinary(eetf::BitBinary {
+                bytes: vec![0b1010_0000],
+                tail_bits_size: 3,
+            }),
+        ] {
+            let Encoded(bytes) = deserialize(term.clone());
+            assert_eq!(
+                Term::decode(io::Cursor::new(bytes)).expect("decode failed"),
+                term
+            );
+        }
+    }
+
+    mod roundtrip {
+        use std::collections::BTreeMap;
+
+        use quickcheck::{Arbitrary, Gen};
+        use serde::de::DeserializeOwned;
+        use serde::Serialize;
+
+        use crate::{
+            from_bytes, from_bytes_with_config, to_bytes, to_bytes_with_config, DeserializerConfig,
+            MapKeyFormat, SerializerConfig, VariantFormat,
+        };
+
+        #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+        enum Shape {
+            Empty,
+            Circle(u8),
+            Rect(i32, i32),
+            Named { name: String, sides: u16 },
+        }
+
+        impl Arbitrary for Shape {
+            fn arbitrary<G: Gen>(g: &mut G) -> Self {
+                match u8::arbitrary(g) % 4 {
+                    0 => Shape::Empty,
+                    1 => Shape::Circle(u8::arbitrary(g)),
+                    2 => Shape::Rect(i32::arbitrary(g), i32::arbitrary(g)),
+                    _ => Shape::Named {
+                        name: String::arbitrary(g),
+                        sides: u16::arbitrary(g),
+                    },
+                }
+            }
+        }
+
+        #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+        struct Pair(i16, String);
+
+        #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+        struct Node {
+            id: u32,
+            name: String,
+            weight: f64,
+            flag: bool,
+            letter: char,
+            big: i64,
+            huge: u64,
+            tags: Vec<String>,
+            counts: BTreeMap<String, i64>,
+            lookup: BTreeMap<i32, Shape>,
+            shape: Option<Shape>,
+            pair: Pair,
+            children: Vec<Node>,
+        }
+
+        impl Node {
+            fn generate<G: Gen>(g: &mut G, depth: usize) -> Self {
+                let children = if depth == 0 {
+                    vec![]
+                } else {
+                    (0..u8::arbitrary(g) % 3)
+                        .map(|_| Node::generate(g, depth - 1))
+                        .collect()
+                };
+                Node {
+                    id: u32::arbitrary(g),
+                    name: String::arbitrary(g),
+                    weight: f64::arbitrary(g),
+                    flag: bool::arbitrary(g),
+                    letter: char::arbitrary(g),
+                    big: i64::arbitrary(g),
+                    huge: u64::arbitrary(g),
+                    tags: Vec::arbitrary(g),
+                    counts: BTreeMap::arbitrary(g),
+                    lookup: BTreeMap::arbitrary(g),
+                    shape: Option::arbitrary(g),
+                    pair: Pair(i16::arbitrary(g), String::arbitrary(g)),
+                    children,
+                }
+            }
+        }
+
+        impl Arbitrary for Node {
+            fn arbitrary<G: Gen>(g: &mut G) -> Self {
+                Node::generate(g, 2)
+            }
+        }
+
+        fn roundtrips<T>(value: &T) -> bool
+        where
+            T: Serialize + DeserializeOwned + PartialEq,
+        {
+            let bytes = to_bytes(value).expect("serialize failed");
+            from_bytes::<T>(&bytes).as_ref() == Ok(value)
+        }
+
+        quickcheck! {
+            fn prop_node_roundtrip(node: Node) -> bool {
+                roundtrips(&node)
+            }
+
+            fn prop_flat_tuple_variants_roundtrip(shapes: Vec<Shape>) -> bool {
+                let config = SerializerConfig {
+                    variant_format: VariantFormat::FlatTuple,
+                    ..SerializerConfig::default()
+                };
+                let bytes = to_bytes_with_config(&shapes, &config).expect("serialize failed");
+                let de_config = DeserializerConfig {
+                    variant_format: VariantFormat::FlatTuple,
+                    ..DeserializerConfig::default()
+                };
+                from_bytes_with_config::<Vec<Shape>>(&bytes, &de_config) == Ok(shapes)
+            }
+
+            fn prop_atom_map_keys_roundtrip(value: BTreeMap<String, u8>) -> bool {
+                let config = SerializerConfig {
+                    map_key_format: MapKeyFormat::SafeAtom,
+                    ..SerializerConfig::default()
+                };
+                let bytes = to_bytes_with_config(&value, &config).expect("serialize failed");
+                from_bytes(&bytes) == Ok(value)
+            }
+
+            fn prop_nested_collections_roundtrip(
+                value: BTreeMap<String, Vec<Option<(i64, bool)>>>
+            ) -> bool {
+                roundtrips(&value)
+            }
+        }
+    }
 }
@@ -0,0 +1,129 @@
+//! Lossless representations of `rust_decimal::Decimal`, for use with
+//! `#[serde(with)]`.  Only available with the `rust_decimal` feature.
+//!
+//! Neither representation goes via `f64`, so no precision is lost.
+//!
+//! ```rust
+//! # #[macro_use]
+//! # extern crate serde_derive;
+//! # extern crate rust_decimal;
+//! # extern crate serde_eetf;
+//! # fn main() {
+//! use rust_decimal::Decimal;
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Payment {
+//!     // Serialized as {Mantissa, Scale}
+//!     #[serde(with = "serde_eetf::decimal::tuple")]
+//!     amount: Decimal,
+//!     // Serialized as a binary, e.g. <<"0.20">>
+//!     #[serde(with = "serde_eetf::decimal::string")]
+//!     fee: Decimal,
+//! }
+//! # }
+//! ```
+
+/// Represents a `Decimal` as a `{Mantissa, Scale}` tuple of integers, where
+/// the value is `Mantissa * 10^-Scale`.
+pub mod tuple {
+    use rust_decimal::Decimal;
+    use serde::de::{self, Deserialize, Deserializer};
+    use serde::ser::{Serialize, Serializer};
+
+    // The limits of what rust_decimal can represent.
+    const MAX_SCALE: u32 = 28;
+    const MAX_MANTISSA: i128 = (1 << 96) - 1;
+
+    pub fn serialize<S>(value: &Decimal, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        (value.mantissa(), value.scale()).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Decimal, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let (mantissa, scale): (i128, u32) = Deserialize::deserialize(deserializer)?;
+        if scale > MAX_SCALE {
+            return Err(de::Error::custom("decimal scale out of range"));
+        }
+        if mantissa > MAX_MANTISSA || mantissa < -MAX_MANTISSA {
+            return Err(de::Error::custom("decimal mantissa out of range"));
+        }
+        Ok(Decimal::from_i128_with_scale(mantissa, scale))
+    }
+}
+
+/// Represents a `Decimal` as its canonical string form, in a binary.
+pub mod string {
+    use std::str::FromStr;
+
+    use rust_decimal::Decimal;
+    use serde::de::{self, Deserialize, Deserializer};
+    use serde::ser::Serializer;
+
+    pub fn serialize<S>(value: &Decimal, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&value.to_string())
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Decimal, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let string = String::deserialize(deserializer)?;
+        Decimal::from_str(&string).map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use rust_decimal::Decimal;
+
+    use crate::{from_bytes, to_bytes};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Amounts {
+        #[serde(with = "crate::decimal::tuple")]
+        as_tuple: Decimal,
+        #[serde(with = "crate::decimal::string")]
+        as_string: Decimal,
+    }
+
+    fn roundtrip(value: &str) {
+        let decimal = Decimal::from_str(value).expect("invalid decimal");
+        let amounts = Amounts {
+            as_tuple: decimal,
+            as_string: decimal,
+        };
+
+        let bytes = to_bytes(&amounts).expect("serialize failed");
+        let result: Amounts = from_bytes(&bytes).expect("deserialize failed");
+
+        assert_eq!(result, amounts);
+        assert_eq!(result.as_tuple.to_string(), value);
+        assert_eq!(result.as_string.to_string(), value);
+    }
+
+    #[test]
+    fn test_small_decimal() {
+        roundtrip("0.1");
+    }
+
+    #[test]
+    fn test_large_decimal() {
+        roundtrip("79228162514264337593543950335");
+        roundtrip("-7922816251426433759354395.0335");
+    }
+
+    #[test]
+    fn test_tiny_decimal() {
+        roundtrip("0.0000000000000000000000000001");
+    }
+}
@@ -1,5 +1,7 @@
 use std;
 use std::fmt::{self, Display};
+use std::io;
+use std::sync::Arc;
 
 use eetf;
 use serde::{de, ser};
@@ -12,8 +14,7 @@ pub type Result<T> = std::result::Result<T, Error>;
 pub enum Error {
     Message(String),
 
-    //TODO: DecodeError(eetf::DecodeError),
-    DecodeError(String),
+    DecodeError(DecodeError),
     EncodeError(String),
     TypeHintsRequired,
     ExpectedBoolean,
@@ -34,6 +35,48 @@ pub enum Error {
     TooManyItems,
     MisSizedVariantTuple,
     ExpectedAtomOrTuple,
+
+    /// A map entry held `nil`, `undefined` or the configured `none_atom`, but
+    /// was deserialized into a type that isn't an `Option`.  Holds the key of
+    /// the entry.
+    UnexpectedNil(String),
+
+    /// Terms were nested deeper than `DeserializerConfig::max_depth`.
+    DepthLimitExceeded,
+
+    /// A term held more atoms than `DeserializerConfig::max_atoms`.
+    TooManyAtoms,
+
+    /// A term was larger than the limit passed to `from_reader_with_limit`.
+    InputTooLarge,
+
+    /// The input was empty, or ended part way through a term.  When reading
+    /// frames from a socket, this usually means more data is needed rather
+    /// than that the data is corrupt.
+    UnexpectedEof,
+
+    /// The term wasn't of the type that was expected, e.g. a binary where a
+    /// map was expected.  Both are described like `"map"` or `"binary"`, &
+    /// `term` holds the term that was found, truncated if it's long.
+    TypeMismatch {
+        expected: &'static str,
+        got: &'static str,
+        term: String,
+    },
+
+    /// A type mismatch reported by serde, e.g. a visitor that can't accept the
+    /// kind of term that was present.
+    InvalidType {
+        expected: String,
+        found: String,
+    },
+
+    /// A value of the right type that serde couldn't accept, e.g. an integer
+    /// out of the range the visitor supports.
+    InvalidValue {
+        expected: String,
+        found: String,
+    },
 }
 
 impl ser::Error for Error {
@@ -46,46 +89,194 @@ impl de::Error for Error {
     fn custom<T: Display>(msg: T) -> Self {
         Error::Message(msg.to_string())
     }
+
+    fn invalid_type(unexp: de::Unexpected, exp: &dyn de::Expected) -> Self {
+        Error::InvalidType {
+            expected: exp.to_string(),
+            found: unexp.to_string(),
+        }
+    }
+
+    fn invalid_value(unexp: de::Unexpected, exp: &dyn de::Expected) -> Self {
+        Error::InvalidValue {
+            expected: exp.to_string(),
+            found: unexp.to_string(),
+        }
+    }
 }
 
 impl Display for Error {
     fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-        formatter.write_str(std::error::Error::description(self))
+        match *self {
+            Error::Message(ref msg) => formatter.write_str(msg),
+            Error::DecodeError(ref err) => write!(formatter, "Decode error: {}", err),
+            Error::EncodeError(ref msg) => write!(formatter, "Encode error: {}", msg),
+            Error::TypeHintsRequired => {
+                formatter.write_str("Type Hints are required for deserializing eetf")
+            }
+            Error::ExpectedBoolean => formatter.write_str("Expected boolean, got something else"),
+            Error::InvalidBoolean => formatter.write_str("Invalid boolean"),
+            Error::ExpectedFixInteger => {
+                formatter.write_str("Expected fix integer, got something else")
+            }
+            Error::ExpectedFloat => {
+                formatter.write_str("Expected float integer, got something else")
+            }
+            Error::ExpectedChar => {
+                formatter.write_str("Expected string of one character, got something else")
+            }
+            Error::ExpectedBinary => formatter.write_str("Expected binary, got something else"),
+            Error::Utf8DecodeError => formatter.write_str("Error decoding UTF8 from binary"),
+            Error::ExpectedNil => formatter.write_str("Expected nil, got something else"),
+            Error::ExpectedList => formatter.write_str("Expected list, got something else"),
+            Error::ExpectedTuple => formatter.write_str("Expected tuple, got something else"),
+            Error::WrongTupleLength => formatter.write_str("Tuple was wrong length"),
+            Error::ExpectedMap => formatter.write_str("Expected map, got something else"),
+            Error::ExpectedAtom => formatter.write_str("Expected atom, got something else"),
+            Error::IntegerConvertError => {
+                formatter.write_str("Could not convert integer without overflow")
+            }
+            Error::FloatConvertError => {
+                formatter.write_str("Could not convert float without overflow")
+            }
+            Error::TooManyItems => {
+                formatter.write_str("Too many items when deserializing sequence")
+            }
+            Error::MisSizedVariantTuple => {
+                formatter.write_str("Was expecting a tuple of an atom and element")
+            }
+            Error::ExpectedAtomOrTuple => formatter.write_str("Was expecting an atom or a tuple"),
+            Error::DepthLimitExceeded => formatter.write_str("Terms were nested too deeply"),
+            Error::TooManyAtoms => formatter.write_str("Term contained too many atoms"),
+            Error::InputTooLarge => formatter.write_str("Term was larger than the size limit"),
+            Error::UnexpectedEof => formatter.write_str("Input ended before a full term was read"),
+            Error::TypeMismatch {
+                expected,
+                got,
+                ref term,
+            } => write!(formatter, "Expected {}, got {}: {}", expected, got, term),
+            Error::UnexpectedNil(ref field) => {
+                write!(formatter, "Field `{}` was nil, but is not optional", field)
+            }
+            Error::InvalidType {
+                ref expected,
+                ref found,
+            } => write!(formatter, "invalid type: {}, expected {}", found, expected),
+            Error::InvalidValue {
+                ref expected,
+                ref found,
+            } => write!(formatter, "invalid value: {}, expected {}", found, expected),
+        }
     }
 }
 
 impl std::error::Error for Error {
-    fn description(&self) -> &str {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match *self {
-            Error::Message(ref msg) => msg,
-            Error::DecodeError(_) => "Decode error",
-            Error::EncodeError(ref msg) => msg,
-            Error::TypeHintsRequired => "Type Hints are required for deserializing eetf",
-            Error::ExpectedBoolean => "Expected boolean, got something else",
-            Error::InvalidBoolean => "Invalid boolean",
-            Error::ExpectedFixInteger => "Expected fix integer, got something else",
-            Error::ExpectedFloat => "Expected float integer, got something else",
-            Error::ExpectedChar => "Expected string of one character, got something else",
-            Error::ExpectedBinary => "Expected binary, got something else",
-            Error::Utf8DecodeError => "Error decoding UTF8 from binary",
-            Error::ExpectedNil => "Expected nil, got something else",
-            Error::ExpectedList => "Expected list, got something else",
-            Error::ExpectedTuple => "Expected tuple, got something else",
-            Error::WrongTupleLength => "Tuple was wrong length",
-            Error::ExpectedMap => "Expected map, got something else",
-            Error::ExpectedAtom => "Expected atom, got something else",
-            Error::IntegerConvertError => "Could not convert integer without overflow",
-            Error::FloatConvertError => "Could not convert float without overflow",
-            Error::TooManyItems => "Too many items when deserializing sequence",
-            Error::MisSizedVariantTuple => "Was expecting a tuple of an atom and element",
-            Error::ExpectedAtomOrTuple => "Was expecting an atom or a tuple",
+            Error::DecodeError(ref err) => Some(err.inner()),
+            _ => None,
         }
     }
 }
 
+/// The error that the eetf crate reported when decoding failed.
+///
+/// This wraps `eetf::DecodeError` so that our `Error` can still be cloned &
+/// compared.  Errors are equal if their messages are.
+#[derive(Clone, Debug)]
+pub struct DecodeError(Arc<eetf::DecodeError>);
+
+impl DecodeError {
+    /// The underlying error.
+    pub fn inner(&self) -> &eetf::DecodeError {
+        &self.0
+    }
+}
+
+impl PartialEq for DecodeError {
+    fn eq(&self, other: &DecodeError) -> bool {
+        self.0.to_string() == other.0.to_string()
+    }
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(formatter)
+    }
+}
+
+impl From<eetf::EncodeError> for Error {
+    fn from(err: eetf::EncodeError) -> Error {
+        Error::EncodeError(err.to_string())
+    }
+}
+
 impl From<eetf::DecodeError> for Error {
     fn from(err: eetf::DecodeError) -> Error {
-        use std::error::Error;
-        self::Error::DecodeError(err.description().to_string())
+        match err {
+            eetf::DecodeError::Io(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => {
+                Error::UnexpectedEof
+            }
+            err => Error::DecodeError(DecodeError(Arc::new(err))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::error::Error as StdError;
+
+    use crate::from_bytes;
+
+    #[test]
+    fn test_decode_error_details() {
+        // A version byte followed by an unknown tag.
+        let result: Result<u8> = from_bytes(&[131, 0]);
+
+        let err = result.expect_err("deserialize succeeded");
+        match err {
+            Error::DecodeError(ref inner) => {
+                assert_eq!(err.to_string(), format!("Decode error: {}", inner.inner()))
+            }
+            ref other => panic!("Expected a DecodeError, got {:?}", other),
+        }
+        assert!(err.source().is_some());
+    }
+
+    #[test]
+    fn test_unexpected_eof() {
+        assert_eq!(from_bytes::<u8>(&[]), Err(Error::UnexpectedEof));
+        // A version byte & the start of a binary that should be 5 bytes long.
+        assert_eq!(
+            from_bytes::<Vec<u8>>(&[131, 109, 0, 0, 0, 5, 1, 2]),
+            Err(Error::UnexpectedEof)
+        );
+    }
+
+    #[test]
+    fn test_display_includes_messages() {
+        assert_eq!(
+            Error::Message("missing field `x`".to_string()).to_string(),
+            "missing field `x`"
+        );
+        assert_eq!(
+            Error::EncodeError("atom too long".to_string()).to_string(),
+            "Encode error: atom too long"
+        );
+        assert_eq!(
+            Error::ExpectedMap.to_string(),
+            "Expected map, got something else"
+        );
+        assert_eq!(
+            Error::TypeMismatch {
+                expected: "map",
+                got: "tuple",
+                term: "{a,b}".to_string()
+            }
+            .to_string(),
+            "Expected map, got tuple: {a,b}"
+        );
     }
 }
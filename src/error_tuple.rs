@@ -0,0 +1,246 @@
+//! Support for the tuples Erlang uses to report errors.
+
+use std::fmt;
+use std::marker::PhantomData;
+
+use serde::de::{self, Deserialize, Deserializer, SeqAccess, Visitor};
+use serde::ser::{Serialize, SerializeTuple, Serializer};
+
+/// The class of an Erlang exception.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ErrorClass {
+    Error,
+    Exit,
+    Throw,
+}
+
+impl ErrorClass {
+    fn name(self) -> &'static str {
+        match self {
+            ErrorClass::Error => "error",
+            ErrorClass::Exit => "exit",
+            ErrorClass::Throw => "throw",
+        }
+    }
+}
+
+impl Serialize for ErrorClass {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_unit_variant("ErrorClass", *self as u32, self.name())
+    }
+}
+
+impl<'de> Deserialize<'de> for ErrorClass {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(ErrorClassVisitor)
+    }
+}
+
+struct ErrorClassVisitor;
+
+impl<'de> Visitor<'de> for ErrorClassVisitor {
+    type Value = ErrorClass;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("one of the atoms error, exit or throw")
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<ErrorClass, E>
+    where
+        E: de::Error,
+    {
+        match v {
+            "error" => Ok(ErrorClass::Error),
+            "exit" => Ok(ErrorClass::Exit),
+            "throw" => Ok(ErrorClass::Throw),
+            _ => Err(de::Error::invalid_value(de::Unexpected::Str(v), &self)),
+        }
+    }
+}
+
+/// An error tuple, as returned by Erlang functions & RPC calls.
+///
+/// When `class` is `None` this is the standard `{error, Reason}`.  When it is
+/// set this is `{error, Class, Reason}`, which preserves the class of an
+/// exception caught on the remote side.  Either form can be deserialized.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ErrorTuple<R> {
+    pub class: Option<ErrorClass>,
+    pub reason: R,
+}
+
+impl<R> ErrorTuple<R> {
+    /// Creates an `{error, Reason}` tuple.
+    pub fn new(reason: R) -> Self {
+        ErrorTuple {
+            class: None,
+            reason,
+        }
+    }
+
+    /// Creates an `{error, Class, Reason}` tuple.
+    pub fn with_class(class: ErrorClass, reason: R) -> Self {
+        ErrorTuple {
+            class: Some(class),
+            reason,
+        }
+    }
+}
+
+impl<R> Serialize for ErrorTuple<R>
+where
+    R: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self.class {
+            Some(class) => {
+                let mut tuple = serializer.serialize_tuple(3)?;
+                tuple.serialize_element(&ErrorClass::Error)?;
+                tuple.serialize_element(&class)?;
+                tuple.serialize_element(&self.reason)?;
+                tuple.end()
+            }
+            None => {
+                let mut tuple = serializer.serialize_tuple(2)?;
+                tuple.serialize_element(&ErrorClass::Error)?;
+                tuple.serialize_element(&self.reason)?;
+                tuple.end()
+            }
+        }
+    }
+}
+
+impl<'de, R> Deserialize<'de> for ErrorTuple<R>
+where
+    R: Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(ErrorTupleVisitor(PhantomData))
+    }
+}
+
+struct ErrorTupleVisitor<R>(PhantomData<R>);
+
+impl<'de, R> Visitor<'de> for ErrorTupleVisitor<R>
+where
+    R: Deserialize<'de>,
+{
+    type Value = ErrorTuple<R>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("{error, Reason} or {error, Class, Reason}")
+    }
+
+    // The form is picked from the length of the tuple, which our deserializer
+    // always provides as the size hint.
+    fn visit_seq<A>(self, mut seq: A) -> Result<ErrorTuple<R>, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let len = match seq.size_hint() {
+            Some(len) if len == 2 || len == 3 => len,
+            Some(len) => return Err(de::Error::invalid_length(len, &self)),
+            None => return Err(de::Error::custom("error tuple length is unknown")),
+        };
+
+        // Every error tuple starts with the atom error, same as the class.
+        match seq.next_element()? {
+            Some(ErrorClass::Error) => (),
+            Some(_) => return Err(de::Error::custom("expected the atom error")),
+            None => return Err(de::Error::invalid_length(0, &self)),
+        }
+        let class = if len == 3 {
+            match seq.next_element()? {
+                Some(class) => Some(class),
+                None => return Err(de::Error::invalid_length(1, &self)),
+            }
+        } else {
+            None
+        };
+        match seq.next_element()? {
+            Some(reason) => Ok(ErrorTuple { class, reason }),
+            None => Err(de::Error::invalid_length(len - 1, &self)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io;
+
+    use eetf::{self, Term};
+
+    use crate::{from_bytes, to_bytes};
+
+    fn atom(name: &str) -> Term {
+        Term::Atom(eetf::Atom::from(name))
+    }
+
+    fn decode(bytes: &[u8]) -> Term {
+        Term::decode(io::Cursor::new(bytes)).expect("decode failed")
+    }
+
+    #[test]
+    fn test_two_tuple_error() {
+        let error = ErrorTuple::new("timeout".to_string());
+
+        let bytes = to_bytes(&error).expect("serialize failed");
+        assert_eq!(
+            decode(&bytes),
+            Term::Tuple(eetf::Tuple::from(vec![
+                atom("error"),
+                Term::Binary(eetf::Binary::from("timeout".as_bytes())),
+            ]))
+        );
+
+        let result: ErrorTuple<String> = from_bytes(&bytes).expect("deserialize failed");
+        assert_eq!(result, error);
+    }
+
+    #[test]
+    fn test_three_tuple_error() {
+        let error = ErrorTuple::with_class(ErrorClass::Exit, "shutdown".to_string());
+
+        let bytes = to_bytes(&error).expect("serialize failed");
+        assert_eq!(
+            decode(&bytes),
+            Term::Tuple(eetf::Tuple::from(vec![
+                atom("error"),
+                atom("exit"),
+                Term::Binary(eetf::Binary::from("shutdown".as_bytes())),
+            ]))
+        );
+
+        let result: ErrorTuple<String> = from_bytes(&bytes).expect("deserialize failed");
+        assert_eq!(result, error);
+    }
+
+    #[test]
+    fn test_rejects_ok_tuples() {
+        let mut cursor = io::Cursor::new(vec![]);
+        Term::Tuple(eetf::Tuple::from(vec![
+            atom("ok"),
+            Term::FixInteger(eetf::FixInteger::from(1)),
+        ]))
+        .encode(&mut cursor)
+        .expect("encode failed");
+
+        let result: crate::Result<ErrorTuple<u8>> = from_bytes(&cursor.into_inner());
+
+        assert!(result.is_err());
+    }
+}
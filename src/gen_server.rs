@@ -0,0 +1,100 @@
+//! Helpers for building the messages that OTP's `gen_server` exchanges.
+//!
+//! These are useful when implementing a C-node or some other raw process that
+//! needs to talk to a `gen_server` directly.  Only available with the
+//! `gen_server` feature.
+
+use eetf::{self, Term};
+use serde::Serialize;
+
+use crate::error::Result;
+use crate::ser::to_term;
+
+/// Builds the `{'$gen_call', {From, Ref}, Request}` message that
+/// `gen_server:call` sends.
+///
+/// `from` should be the pid of the calling process and `reference` a
+/// reference that identifies this call.  The reply will be sent to `from` as
+/// `{Ref, Reply}`.
+pub fn call_message<T>(from: Term, reference: Term, request: &T) -> Result<Term>
+where
+    T: Serialize + ?Sized,
+{
+    Ok(Term::Tuple(eetf::Tuple::from(vec![
+        Term::Atom(eetf::Atom::from("$gen_call")),
+        Term::Tuple(eetf::Tuple::from(vec![from, reference])),
+        to_term(request)?,
+    ])))
+}
+
+/// Builds the `{'$gen_cast', Request}` message that `gen_server:cast` sends.
+pub fn cast_message<T>(request: &T) -> Result<Term>
+where
+    T: Serialize + ?Sized,
+{
+    Ok(Term::Tuple(eetf::Tuple::from(vec![
+        Term::Atom(eetf::Atom::from("$gen_cast")),
+        to_term(request)?,
+    ])))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize)]
+    enum Request {
+        Lookup(String),
+    }
+
+    fn lookup_term() -> Term {
+        Term::Tuple(eetf::Tuple::from(vec![
+            Term::Atom(eetf::Atom::from("lookup")),
+            Term::Binary(eetf::Binary::from("key".as_bytes())),
+        ]))
+    }
+
+    #[test]
+    fn test_call_message() {
+        let from = Term::Pid(eetf::Pid {
+            node: eetf::Atom::from("node@localhost"),
+            id: 1,
+            serial: 0,
+            creation: 0,
+        });
+        let reference = Term::Reference(eetf::Reference {
+            node: eetf::Atom::from("node@localhost"),
+            id: vec![1, 2, 3],
+            creation: 0,
+        });
+
+        let result = call_message(
+            from.clone(),
+            reference.clone(),
+            &Request::Lookup("key".to_string()),
+        )
+        .expect("serialize failed");
+
+        assert_eq!(
+            result,
+            Term::Tuple(eetf::Tuple::from(vec![
+                Term::Atom(eetf::Atom::from("$gen_call")),
+                Term::Tuple(eetf::Tuple::from(vec![from, reference])),
+                lookup_term(),
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_cast_message() {
+        let result = cast_message(&Request::Lookup("key".to_string())).expect("serialize failed");
+
+        assert_eq!(
+            result,
+            Term::Tuple(eetf::Tuple::from(vec![
+                Term::Atom(eetf::Atom::from("$gen_cast")),
+                lookup_term(),
+            ]))
+        );
+    }
+}
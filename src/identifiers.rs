@@ -0,0 +1,283 @@
+//! Support for the identifiers Erlang uses for processes, references &
+//! ports.
+
+use eetf::{self, Term};
+use serde::de::{Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
+
+use crate::value;
+
+/// An Erlang process identifier.
+///
+/// Messages often carry pids, e.g. the caller of a `gen_server:call`.  These
+/// are opaque to rust code, but can be deserialized so they can be stored,
+/// compared or sent back.  Like the other identifiers, this is
+/// (de)serialized as an `EetfValue`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Pid {
+    /// The name of the node the process is running on.
+    pub node: String,
+    pub id: u32,
+    pub serial: u32,
+    pub creation: u8,
+}
+
+impl From<eetf::Pid> for Pid {
+    fn from(pid: eetf::Pid) -> Self {
+        Pid {
+            node: pid.node.name,
+            id: pid.id,
+            serial: pid.serial,
+            creation: pid.creation,
+        }
+    }
+}
+
+impl From<Pid> for eetf::Pid {
+    fn from(pid: Pid) -> Self {
+        eetf::Pid {
+            node: eetf::Atom::from(pid.node),
+            id: pid.id,
+            serial: pid.serial,
+            creation: pid.creation,
+        }
+    }
+}
+
+/// An Erlang reference, e.g. from `make_ref/0`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Reference {
+    /// The name of the node the reference was made on.
+    pub node: String,
+    pub id: Vec<u32>,
+    pub creation: u8,
+}
+
+impl From<eetf::Reference> for Reference {
+    fn from(reference: eetf::Reference) -> Self {
+        Reference {
+            node: reference.node.name,
+            id: reference.id,
+            creation: reference.creation,
+        }
+    }
+}
+
+impl From<Reference> for eetf::Reference {
+    fn from(reference: Reference) -> Self {
+        eetf::Reference {
+            node: eetf::Atom::from(reference.node),
+            id: reference.id,
+            creation: reference.creation,
+        }
+    }
+}
+
+/// An Erlang port identifier.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Port {
+    /// The name of the node the port was opened on.
+    pub node: String,
+    pub id: u32,
+    pub creation: u8,
+}
+
+impl From<eetf::Port> for Port {
+    fn from(port: eetf::Port) -> Self {
+        Port {
+            node: port.node.name,
+            id: port.id,
+            creation: port.creation,
+        }
+    }
+}
+
+impl From<Port> for eetf::Port {
+    fn from(port: Port) -> Self {
+        eetf::Port {
+            node: eetf::Atom::from(port.node),
+            id: port.id,
+            creation: port.creation,
+        }
+    }
+}
+
+impl Serialize for Pid {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        value::serialize_term(Term::Pid(self.clone().into()), serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Pid {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        value::deserialize_term(deserializer, "a pid", |term| match term {
+            Term::Pid(pid) => Ok(Pid::from(pid)),
+            other => Err(other),
+        })
+    }
+}
+
+impl Serialize for Reference {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        value::serialize_term(Term::Reference(self.clone().into()), serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Reference {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        value::deserialize_term(deserializer, "a reference", |term| match term {
+            Term::Reference(reference) => Ok(Reference::from(reference)),
+            other => Err(other),
+        })
+    }
+}
+
+impl Serialize for Port {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        value::serialize_term(Term::Port(self.clone().into()), serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Port {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        value::deserialize_term(deserializer, "a port", |term| match term {
+            Term::Port(port) => Ok(Port::from(port)),
+            other => Err(other),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io;
+
+    use crate::{from_bytes, to_bytes};
+
+    fn encode(term: Term) -> Vec<u8> {
+        let mut cursor = io::Cursor::new(vec![]);
+        term.encode(&mut cursor).expect("encode failed");
+        cursor.into_inner()
+    }
+
+    #[test]
+    fn test_deserialize_pid() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Registered {
+            name: String,
+            pid: Pid,
+        }
+
+        let bytes = encode(Term::Map(eetf::Map::from(vec![
+            (
+                Term::Atom(eetf::Atom::from("name")),
+                Term::Binary(eetf::Binary::from("jobs".as_bytes())),
+            ),
+            (
+                Term::Atom(eetf::Atom::from("pid")),
+                Term::Pid(eetf::Pid {
+                    node: eetf::Atom::from("node@localhost"),
+                    id: 42,
+                    serial: 1,
+                    creation: 2,
+                }),
+            ),
+        ])));
+
+        let result: Registered = from_bytes(&bytes).expect("deserialize failed");
+
+        assert_eq!(
+            result,
+            Registered {
+                name: "jobs".to_string(),
+                pid: Pid {
+                    node: "node@localhost".to_string(),
+                    id: 42,
+                    serial: 1,
+                    creation: 2,
+                },
+            }
+        );
+    }
+
+    #[test]
+    fn test_pid_roundtrip() {
+        let pid = Pid {
+            node: "node@localhost".to_string(),
+            id: 42,
+            serial: 1,
+            creation: 2,
+        };
+
+        let bytes = to_bytes(&pid).expect("serialize failed");
+        assert_eq!(
+            Term::decode(io::Cursor::new(&bytes)).expect("decode failed"),
+            Term::Pid(pid.clone().into())
+        );
+
+        let result: Pid = from_bytes(&bytes).expect("deserialize failed");
+        assert_eq!(result, pid);
+    }
+
+    #[test]
+    fn test_echo_identifiers() {
+        let pid = Pid {
+            node: "node@localhost".to_string(),
+            id: 42,
+            serial: 1,
+            creation: 2,
+        };
+        let reference = Reference {
+            node: "node@localhost".to_string(),
+            id: vec![1, 2, 3],
+            creation: 2,
+        };
+        let port = Port {
+            node: "node@localhost".to_string(),
+            id: 7,
+            creation: 2,
+        };
+        let reply = (reference.clone(), (pid.clone(), port.clone()));
+
+        let bytes = to_bytes(&reply).expect("serialize failed");
+        assert_eq!(
+            Term::decode(io::Cursor::new(&bytes)).expect("decode failed"),
+            Term::Tuple(eetf::Tuple::from(vec![
+                Term::Reference(reference.into()),
+                Term::Tuple(eetf::Tuple::from(vec![
+                    Term::Pid(pid.into()),
+                    Term::Port(port.into()),
+                ])),
+            ]))
+        );
+
+        let result: (Reference, (Pid, Port)) = from_bytes(&bytes).expect("deserialize failed");
+        assert_eq!(result, reply);
+    }
+
+    #[test]
+    fn test_deserialize_pid_rejects_other_terms() {
+        let bytes = encode(Term::Atom(eetf::Atom::from("self")));
+
+        assert!(from_bytes::<Pid>(&bytes).is_err());
+    }
+}
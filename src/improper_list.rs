@@ -0,0 +1,87 @@
+//! Support for Erlang's improper lists.
+
+use eetf::{self, Term};
+use serde::de::{Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
+
+use crate::value;
+
+/// A list whose tail isn't `[]`, e.g. `[1, 2 | 3]`.
+///
+/// These are rare outside of iolists, but deserializing one any other way
+/// loses track of which term was the tail.  This is (de)serialized as an
+/// `EetfValue`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImproperList {
+    pub elements: Vec<Term>,
+    pub tail: Term,
+}
+
+impl From<eetf::ImproperList> for ImproperList {
+    fn from(list: eetf::ImproperList) -> Self {
+        ImproperList {
+            elements: list.elements,
+            tail: *list.last,
+        }
+    }
+}
+
+impl From<ImproperList> for eetf::ImproperList {
+    fn from(list: ImproperList) -> Self {
+        eetf::ImproperList {
+            elements: list.elements,
+            last: Box::new(list.tail),
+        }
+    }
+}
+
+impl Serialize for ImproperList {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        value::serialize_term(Term::ImproperList(self.clone().into()), serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for ImproperList {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        value::deserialize_term(deserializer, "an improper list", |term| match term {
+            Term::ImproperList(list) => Ok(ImproperList::from(list)),
+            other => Err(other),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io;
+
+    use crate::{from_bytes, to_bytes};
+
+    fn int(value: i32) -> Term {
+        Term::FixInteger(eetf::FixInteger::from(value))
+    }
+
+    #[test]
+    fn test_improper_list_roundtrip() {
+        let list = ImproperList {
+            elements: vec![int(1), int(2)],
+            tail: int(3),
+        };
+
+        let bytes = to_bytes(&list).expect("serialize failed");
+        assert_eq!(
+            Term::decode(io::Cursor::new(&bytes)).expect("decode failed"),
+            Term::ImproperList(list.clone().into())
+        );
+
+        let result: ImproperList = from_bytes(&bytes).expect("deserialize failed");
+        assert_eq!(result, list);
+    }
+}
@@ -0,0 +1,267 @@
+//! Support for Erlang iolists.
+
+use std::fmt;
+
+use serde::de::{self, Deserialize, Deserializer, SeqAccess, Visitor};
+use serde::ser::{Serialize, SerializeSeq, Serializer};
+
+/// An Erlang iolist: a possibly nested list of binaries & bytes.
+///
+/// Building output as an iolist lets Erlang write it out with functions like
+/// `gen_tcp:send/2` without first copying it into one large binary.
+/// `iolist_to_binary/1` concatenates everything in order.
+///
+/// ```rust
+/// # extern crate serde_eetf;
+/// # fn main() {
+/// use serde_eetf::{to_bytes, IoData, IoList};
+///
+/// let iolist = IoList(vec![
+///     IoData::Binary(b"HTTP/1.1 ".to_vec()),
+///     IoData::List(IoList(vec![IoData::Byte(b'2'), IoData::Byte(b'0')])),
+///     IoData::Byte(b'0'),
+/// ]);
+/// let bytes = to_bytes(&iolist).unwrap();
+/// # }
+/// ```
+///
+/// An `IoList` can also be deserialized from a (proper) iolist sent by Erlang,
+/// and then flattened into bytes with `IoList::flatten`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct IoList(pub Vec<IoData>);
+
+impl IoList {
+    /// Concatenates all the bytes in this iolist, like `iolist_to_binary/1`.
+    pub fn flatten(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        self.flatten_into(&mut bytes);
+        bytes
+    }
+
+    fn flatten_into(&self, bytes: &mut Vec<u8>) {
+        for element in &self.0 {
+            match element {
+                IoData::Binary(binary) => bytes.extend_from_slice(binary),
+                IoData::Byte(byte) => bytes.push(*byte),
+                IoData::List(list) => list.flatten_into(bytes),
+            }
+        }
+    }
+}
+
+/// An element of an `IoList`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum IoData {
+    /// A chunk of bytes, serialized as a binary.
+    Binary(Vec<u8>),
+    /// A single byte, serialized as an integer.
+    Byte(u8),
+    /// A nested iolist.
+    List(IoList),
+}
+
+impl Serialize for IoList {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut seq = serializer.serialize_seq(Some(self.0.len()))?;
+        for element in &self.0 {
+            seq.serialize_element(element)?;
+        }
+        seq.end()
+    }
+}
+
+impl Serialize for IoData {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            IoData::Binary(bytes) => serializer.serialize_bytes(bytes),
+            IoData::Byte(byte) => serializer.serialize_u8(*byte),
+            IoData::List(list) => list.serialize(serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for IoList {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_seq(IoListVisitor)
+    }
+}
+
+impl<'de> Deserialize<'de> for IoData {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(IoDataVisitor)
+    }
+}
+
+struct IoListVisitor;
+
+impl<'de> Visitor<'de> for IoListVisitor {
+    type Value = IoList;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an iolist")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<IoList, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut elements = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(element) = seq.next_element()? {
+            elements.push(element);
+        }
+        Ok(IoList(elements))
+    }
+}
+
+struct IoDataVisitor;
+
+impl<'de> Visitor<'de> for IoDataVisitor {
+    type Value = IoData;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a binary, a byte or an iolist")
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<IoData, E>
+    where
+        E: de::Error,
+    {
+        if v >= 0 && v <= 255 {
+            Ok(IoData::Byte(v as u8))
+        } else {
+            Err(de::Error::invalid_value(de::Unexpected::Signed(v), &self))
+        }
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<IoData, E>
+    where
+        E: de::Error,
+    {
+        if v <= 255 {
+            Ok(IoData::Byte(v as u8))
+        } else {
+            Err(de::Error::invalid_value(de::Unexpected::Unsigned(v), &self))
+        }
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<IoData, E>
+    where
+        E: de::Error,
+    {
+        Ok(IoData::Binary(v.to_vec()))
+    }
+
+    fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<IoData, E>
+    where
+        E: de::Error,
+    {
+        Ok(IoData::Binary(v))
+    }
+
+    fn visit_seq<A>(self, seq: A) -> Result<IoData, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        IoListVisitor.visit_seq(seq).map(IoData::List)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io;
+
+    use eetf::{self, Term};
+
+    use crate::{from_bytes, to_bytes};
+
+    #[test]
+    fn test_serialize_iolist() {
+        let iolist = IoList(vec![
+            IoData::Binary(b"ab".to_vec()),
+            IoData::List(IoList(vec![
+                IoData::Byte(b'c'),
+                IoData::List(IoList(vec![])),
+            ])),
+            IoData::Byte(b'd'),
+        ]);
+
+        let bytes = to_bytes(&iolist).expect("serialize failed");
+
+        assert_eq!(
+            Term::decode(io::Cursor::new(&bytes)).expect("decode failed"),
+            Term::List(eetf::List::from(vec![
+                Term::Binary(eetf::Binary::from("ab".as_bytes())),
+                Term::List(eetf::List::from(vec![
+                    Term::FixInteger(eetf::FixInteger::from(99)),
+                    Term::List(eetf::List::from(vec![])),
+                ])),
+                Term::FixInteger(eetf::FixInteger::from(100)),
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_deserialize_and_flatten_iolist() {
+        let input = Term::List(eetf::List::from(vec![
+            Term::Binary(eetf::Binary::from("he".as_bytes())),
+            Term::List(eetf::List::from(vec![
+                Term::FixInteger(eetf::FixInteger::from(108)),
+                Term::List(eetf::List::from(vec![Term::Binary(eetf::Binary::from(
+                    "lo".as_bytes(),
+                ))])),
+            ])),
+            Term::List(eetf::List::from(vec![])),
+            Term::FixInteger(eetf::FixInteger::from(33)),
+        ]));
+        let mut cursor = io::Cursor::new(vec![]);
+        input.encode(&mut cursor).expect("encode failed");
+
+        let result: IoList = from_bytes(&cursor.into_inner()).expect("deserialize failed");
+
+        assert_eq!(result.flatten(), b"hello!".to_vec());
+    }
+
+    #[test]
+    fn test_deserialize_improper_iolist() {
+        // Iolists may end in a binary rather than [], e.g. [<<"he">> | <<"llo">>].
+        let input = Term::List(eetf::List::from(vec![Term::ImproperList(
+            eetf::ImproperList {
+                elements: vec![Term::Binary(eetf::Binary::from("he".as_bytes()))],
+                last: Box::new(Term::Binary(eetf::Binary::from("llo".as_bytes()))),
+            },
+        )]));
+        let mut cursor = io::Cursor::new(vec![]);
+        input.encode(&mut cursor).expect("encode failed");
+
+        let result: IoList = from_bytes(&cursor.into_inner()).expect("deserialize failed");
+
+        assert_eq!(result.flatten(), b"hello".to_vec());
+    }
+
+    #[test]
+    fn test_deserialize_iolist_rejects_large_integers() {
+        let input = Term::List(eetf::List::from(vec![Term::FixInteger(
+            eetf::FixInteger::from(256),
+        )]));
+        let mut cursor = io::Cursor::new(vec![]);
+        input.encode(&mut cursor).expect("encode failed");
+
+        let result: crate::Result<IoList> = from_bytes(&cursor.into_inner());
+
+        assert!(result.is_err());
+    }
+}
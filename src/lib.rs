@@ -30,6 +30,7 @@
 
 extern crate eetf;
 extern crate heck;
+extern crate libflate;
 extern crate num_bigint;
 extern crate num_traits;
 #[macro_use]
@@ -40,10 +41,59 @@ extern crate serde;
 #[macro_use]
 extern crate serde_derive;
 
+#[cfg(test)]
+#[macro_use]
+extern crate quickcheck;
+
+#[cfg(feature = "tokio")]
+mod async_reader;
+mod atom;
+mod bit_binary;
+mod config;
 mod de;
 mod error;
+mod error_tuple;
+mod identifiers;
+mod improper_list;
+mod iolist;
+mod result_option;
 mod ser;
+mod time;
+mod value;
+
+#[cfg(feature = "rust_decimal")]
+pub mod decimal;
+#[cfg(feature = "gen_server")]
+pub mod gen_server;
+pub mod nested_binary;
 
-pub use crate::de::{from_bytes, from_reader, Deserializer};
-pub use crate::error::{Error, Result};
-pub use crate::ser::{to_bytes, to_writer};
+#[cfg(feature = "tokio")]
+pub use crate::async_reader::from_async_reader;
+pub use crate::atom::Atom;
+pub use crate::bit_binary::BitBinary;
+pub use crate::config::{
+    AtomEncoding, BinaryInterpretation, BoolFormat, CharFormat, DeserializerConfig, EmptyMapFormat,
+    FloatFormat, FloatToInteger, KeyFormat, MapKeyFormat, NanHandling, NewtypeStructFormat,
+    SerializerConfig, StringFormat, StructFormat, TupleStructFormat, UnitFormat, VariantCase,
+    VariantFormat, VariantTag,
+};
+pub use crate::de::{
+    explain_mismatch, explain_mismatch_with_config, for_each_term, from_bytes,
+    from_bytes_by_field_order, from_bytes_by_field_order_with_config, from_bytes_no_version,
+    from_bytes_with_config, from_bytes_with_limit, from_bytes_with_trailing,
+    from_bytes_with_warnings, from_bytes_with_warnings_with_config, from_reader,
+    from_reader_with_config, from_reader_with_limit, from_term, Deserializer, TermStream, Warning,
+};
+pub use crate::error::{DecodeError, Error, Result};
+pub use crate::error_tuple::{ErrorClass, ErrorTuple};
+pub use crate::identifiers::{Pid, Port, Reference};
+pub use crate::improper_list::ImproperList;
+pub use crate::iolist::{IoData, IoList};
+pub use crate::result_option::ResultOption;
+pub use crate::ser::{
+    count_distinct_atoms, count_distinct_atoms_with_config, eetf_eq, to_bytes, to_bytes_compressed,
+    to_bytes_no_version, to_bytes_with_config, to_term, to_writer, to_writer_compressed,
+    to_writer_no_version, to_writer_with_config, Serializer, SerializerBuilder,
+};
+pub use crate::time::FlexibleTime;
+pub use crate::value::{value_from_bytes, value_from_reader, EetfValue};
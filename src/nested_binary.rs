@@ -0,0 +1,166 @@
+//! Support for fields that hold a separately encoded term inside a binary.
+//!
+//! Erlang code sometimes stores the output of `term_to_binary/1` inside another
+//! term as an opaque blob.  Annotating a field with
+//! `#[serde(with = "serde_eetf::nested_binary")]` encodes the field as its own
+//! EETF and stores that in a binary, and does the reverse when deserializing.
+//!
+//! ```rust
+//! # #[macro_use]
+//! # extern crate serde_derive;
+//! # extern crate serde_eetf;
+//! # fn main() {
+//! #[derive(Serialize, Deserialize)]
+//! struct Payload {
+//!     id: u32,
+//! }
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Envelope {
+//!     #[serde(with = "serde_eetf::nested_binary")]
+//!     payload: Payload,
+//! }
+//! # }
+//! ```
+
+use std::fmt;
+
+use serde::de::{self, DeserializeOwned, Deserializer, Visitor};
+use serde::ser::{self, Serialize, Serializer};
+
+use crate::de::from_bytes;
+use crate::ser::to_bytes;
+
+/// Encodes `value` as EETF, then serializes the encoded bytes as a binary.
+pub fn serialize<T, S>(value: &T, serializer: S) -> std::result::Result<S::Ok, S::Error>
+where
+    T: Serialize + ?Sized,
+    S: Serializer,
+{
+    let bytes = to_bytes(value).map_err(ser::Error::custom)?;
+    serializer.serialize_bytes(&bytes)
+}
+
+/// Deserializes a binary containing an encoded term, then deserializes `T`
+/// from that term.
+pub fn deserialize<'de, D, T>(deserializer: D) -> std::result::Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: DeserializeOwned,
+{
+    let bytes = deserializer.deserialize_byte_buf(EncodedTermVisitor)?;
+    from_bytes(&bytes).map_err(de::Error::custom)
+}
+
+struct EncodedTermVisitor;
+
+impl<'de> Visitor<'de> for EncodedTermVisitor {
+    type Value = Vec<u8>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a binary containing an encoded term")
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> std::result::Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(v.to_vec())
+    }
+
+    fn visit_byte_buf<E>(self, v: Vec<u8>) -> std::result::Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(v)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use eetf::{self, Term};
+
+    use crate::{from_bytes, to_bytes};
+
+    #[test]
+    fn test_deserialize_nested_binary() {
+        #[derive(Serialize)]
+        struct Inner {
+            id: u8,
+        }
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct InnerCopy {
+            id: u8,
+        }
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Envelope {
+            #[serde(with = "crate::nested_binary")]
+            payload: InnerCopy,
+        }
+
+        let inner_bytes = to_bytes(&Inner { id: 5 }).expect("serialize failed");
+        let envelope = Term::Map(eetf::Map::from(vec![(
+            Term::Atom(eetf::Atom::from("payload")),
+            Term::Binary(eetf::Binary::from(inner_bytes.as_slice())),
+        )]));
+        let mut cursor = io::Cursor::new(vec![]);
+        envelope.encode(&mut cursor).expect("encode failed");
+
+        let result: Envelope = from_bytes(&cursor.into_inner()).expect("deserialize failed");
+
+        assert_eq!(
+            result,
+            Envelope {
+                payload: InnerCopy { id: 5 }
+            }
+        );
+    }
+
+    #[test]
+    fn test_nested_binary_roundtrip() {
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Inner {
+            name: String,
+            count: u16,
+        }
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Envelope {
+            kind: String,
+            #[serde(with = "crate::nested_binary")]
+            payload: Inner,
+        }
+
+        let envelope = Envelope {
+            kind: "update".to_string(),
+            payload: Inner {
+                name: "test".to_string(),
+                count: 3,
+            },
+        };
+
+        let bytes = to_bytes(&envelope).expect("serialize failed");
+
+        // The payload should have been stored as an opaque binary.
+        let term = Term::decode(io::Cursor::new(&bytes)).expect("decode failed");
+        let payload = match term {
+            Term::Map(map) => map
+                .entries
+                .into_iter()
+                .find(|(key, _)| *key == Term::Atom(eetf::Atom::from("payload")))
+                .map(|(_, value)| value),
+            _ => None,
+        };
+        match payload {
+            Some(Term::Binary(_)) => (),
+            other => panic!("Expected payload to be a binary, got {:?}", other),
+        }
+
+        let result: Envelope = from_bytes(&bytes).expect("deserialize failed");
+        assert_eq!(result, envelope);
+    }
+}
@@ -0,0 +1,209 @@
+//! Support for optional values represented as `{ok, Value}` or `error`.
+
+use std::fmt;
+use std::marker::PhantomData;
+
+use serde::de::{self, Deserialize, Deserializer, IgnoredAny, SeqAccess, Visitor};
+use serde::ser::{Serialize, SerializeTuple, Serializer};
+
+/// An optional value that uses the `{ok, Value}` convention of many Erlang
+/// APIs, e.g. `maps:find/2`.
+///
+/// `Some(value)` is serialized as `{ok, Value}` and `None` as the atom `error`.
+/// When deserializing, `{error, Reason}` is also accepted as `None`, with the
+/// reason discarded.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ResultOption<T>(pub Option<T>);
+
+impl<T> From<Option<T>> for ResultOption<T> {
+    fn from(option: Option<T>) -> Self {
+        ResultOption(option)
+    }
+}
+
+impl<T> From<ResultOption<T>> for Option<T> {
+    fn from(option: ResultOption<T>) -> Self {
+        option.0
+    }
+}
+
+impl<T> Serialize for ResultOption<T>
+where
+    T: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match &self.0 {
+            Some(value) => {
+                let mut tuple = serializer.serialize_tuple(2)?;
+                tuple.serialize_element(&Tag::Ok)?;
+                tuple.serialize_element(value)?;
+                tuple.end()
+            }
+            None => Tag::Error.serialize(serializer),
+        }
+    }
+}
+
+impl<'de, T> Deserialize<'de> for ResultOption<T>
+where
+    T: Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(ResultOptionVisitor(PhantomData))
+    }
+}
+
+struct ResultOptionVisitor<T>(PhantomData<T>);
+
+impl<'de, T> Visitor<'de> for ResultOptionVisitor<T>
+where
+    T: Deserialize<'de>,
+{
+    type Value = ResultOption<T>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("{ok, Value}, error or {error, Reason}")
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<ResultOption<T>, E>
+    where
+        E: de::Error,
+    {
+        if v == "error" {
+            Ok(ResultOption(None))
+        } else {
+            Err(de::Error::invalid_value(de::Unexpected::Str(v), &self))
+        }
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<ResultOption<T>, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        match seq.next_element()? {
+            Some(Tag::Ok) => match seq.next_element()? {
+                Some(value) => Ok(ResultOption(Some(value))),
+                None => Err(de::Error::invalid_length(1, &self)),
+            },
+            Some(Tag::Error) => match seq.next_element::<IgnoredAny>()? {
+                Some(_) => Ok(ResultOption(None)),
+                None => Err(de::Error::invalid_length(1, &self)),
+            },
+            None => Err(de::Error::invalid_length(0, &self)),
+        }
+    }
+}
+
+// The atoms that tag a ResultOption.
+enum Tag {
+    Ok,
+    Error,
+}
+
+impl Serialize for Tag {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            Tag::Ok => serializer.serialize_unit_variant("Tag", 0, "ok"),
+            Tag::Error => serializer.serialize_unit_variant("Tag", 1, "error"),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Tag {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(TagVisitor)
+    }
+}
+
+struct TagVisitor;
+
+impl<'de> Visitor<'de> for TagVisitor {
+    type Value = Tag;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("the atom ok or error")
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Tag, E>
+    where
+        E: de::Error,
+    {
+        match v {
+            "ok" => Ok(Tag::Ok),
+            "error" => Ok(Tag::Error),
+            _ => Err(de::Error::invalid_value(de::Unexpected::Str(v), &self)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io;
+
+    use eetf::{self, Term};
+
+    use crate::{from_bytes, to_bytes};
+
+    fn atom(name: &str) -> Term {
+        Term::Atom(eetf::Atom::from(name))
+    }
+
+    fn decode(bytes: &[u8]) -> Term {
+        Term::decode(io::Cursor::new(bytes)).expect("decode failed")
+    }
+
+    #[test]
+    fn test_some_roundtrip() {
+        let value = ResultOption(Some(5u8));
+
+        let bytes = to_bytes(&value).expect("serialize failed");
+        assert_eq!(
+            decode(&bytes),
+            Term::Tuple(eetf::Tuple::from(vec![
+                atom("ok"),
+                Term::FixInteger(eetf::FixInteger::from(5)),
+            ]))
+        );
+
+        let result: ResultOption<u8> = from_bytes(&bytes).expect("deserialize failed");
+        assert_eq!(result, value);
+    }
+
+    #[test]
+    fn test_none_roundtrip() {
+        let value: ResultOption<u8> = ResultOption(None);
+
+        let bytes = to_bytes(&value).expect("serialize failed");
+        assert_eq!(decode(&bytes), atom("error"));
+
+        let result: ResultOption<u8> = from_bytes(&bytes).expect("deserialize failed");
+        assert_eq!(result, value);
+    }
+
+    #[test]
+    fn test_error_tuple_is_none() {
+        let mut cursor = io::Cursor::new(vec![]);
+        Term::Tuple(eetf::Tuple::from(vec![atom("error"), atom("not_found")]))
+            .encode(&mut cursor)
+            .expect("encode failed");
+
+        let result: ResultOption<u8> =
+            from_bytes(&cursor.into_inner()).expect("deserialize failed");
+
+        assert_eq!(result, ResultOption(None));
+    }
+}
@@ -1,14 +1,25 @@
 use num_bigint::BigInt;
-use num_traits::cast::FromPrimitive;
+use num_traits::cast::ToPrimitive;
 use serde::ser::{self, Serialize};
+use std::cmp::Ordering;
+use std::collections::HashSet;
 use std::convert::TryFrom;
-use std::io;
+use std::io::{self, Write};
+use std::str;
 
 use heck::SnakeCase;
+use libflate::zlib;
 
 use eetf::{self, Term};
 
+use crate::atom;
+use crate::config::{
+    AtomEncoding, BoolFormat, CharFormat, EmptyMapFormat, FloatFormat, KeyFormat, MapKeyFormat,
+    NanHandling, NewtypeStructFormat, SerializerConfig, StringFormat, StructFormat,
+    TupleStructFormat, UnitFormat, VariantCase, VariantFormat, VariantTag,
+};
 use crate::error::{Error, Result};
+use crate::value;
 
 /// Serializes a value into EETF using a Write
 pub fn to_writer<T, W>(value: &T, writer: &mut W) -> Result<()>
@@ -16,45 +27,823 @@ where
     T: Serialize + ?Sized,
     W: io::Write + ?Sized,
 {
-    let serializer = Serializer {};
-    let term = value.serialize(&serializer)?;
-    match term.encode(writer) {
-        Ok(_result) => Ok(()),
-        Err(_error) => Err(Error::EncodeError("TODO".to_string())),
-    }
+    to_writer_with_config(value, writer, &SerializerConfig::default())
 }
 
 /// Serializes a value into a EETF in a Vec of bytes
 pub fn to_bytes<T>(value: &T) -> Result<Vec<u8>>
+where
+    T: Serialize + ?Sized,
+{
+    to_bytes_with_config(value, &SerializerConfig::default())
+}
+
+/// Serializes a value into EETF using a Write, with the given config.
+pub fn to_writer_with_config<T, W>(
+    value: &T,
+    writer: &mut W,
+    config: &SerializerConfig,
+) -> Result<()>
+where
+    T: Serialize + ?Sized,
+    W: io::Write + ?Sized,
+{
+    let term = to_term_with_config(value, config)?;
+    if config.compress {
+        return encode_compressed(&term, writer, config);
+    }
+    encode(&term, writer, config)
+}
+
+/// Serializes a value into compressed EETF using a Write, like
+/// `term_to_binary(Term, [compressed])`.
+pub fn to_writer_compressed<T, W>(value: &T, writer: &mut W) -> Result<()>
+where
+    T: Serialize + ?Sized,
+    W: io::Write + ?Sized,
+{
+    let config = SerializerConfig {
+        compress: true,
+        ..SerializerConfig::default()
+    };
+    to_writer_with_config(value, writer, &config)
+}
+
+/// Serializes a value into compressed EETF in a Vec of bytes, like
+/// `term_to_binary(Term, [compressed])`.
+pub fn to_bytes_compressed<T>(value: &T) -> Result<Vec<u8>>
+where
+    T: Serialize + ?Sized,
+{
+    let mut bytes = Vec::new();
+    to_writer_compressed(value, &mut bytes)?;
+    Ok(bytes)
+}
+
+/// Serializes a value into EETF using a Write, without the version byte that
+/// usually starts it.
+///
+/// `to_writer` produces the same bytes as `term_to_binary/1`, which begin with
+/// the version byte `131`, & that's what `binary_to_term/1`, ports & files
+/// expect.  Some parts of the distribution protocol handle the version at the
+/// frame level instead, e.g. the terms that follow a distribution header, so
+/// need each term without it.  Use `from_bytes_no_version` to deserialize
+/// terms in this form.
+pub fn to_writer_no_version<T, W>(value: &T, writer: &mut W) -> Result<()>
+where
+    T: Serialize + ?Sized,
+    W: io::Write + ?Sized,
+{
+    let mut writer = SkipVersion {
+        inner: writer,
+        skipped: false,
+    };
+    to_writer(value, &mut writer)
+}
+
+/// Serializes a value into EETF in a Vec of bytes, without the version byte
+/// that usually starts it.
+///
+/// See `to_writer_no_version` for when this is needed.
+pub fn to_bytes_no_version<T>(value: &T) -> Result<Vec<u8>>
+where
+    T: Serialize + ?Sized,
+{
+    let mut bytes = Vec::new();
+    to_writer_no_version(value, &mut bytes)?;
+    Ok(bytes)
+}
+
+// A Write that drops the first byte written to it, which the encoder always
+// writes on its own or at the start of a larger write.
+struct SkipVersion<'a, W: ?Sized> {
+    inner: &'a mut W,
+    skipped: bool,
+}
+
+impl<'a, W> io::Write for SkipVersion<'a, W>
+where
+    W: io::Write + ?Sized,
+{
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.skipped || buf.is_empty() {
+            return self.inner.write(buf);
+        }
+        let written = self.inner.write(&buf[1..])?;
+        self.skipped = true;
+        Ok(written + 1)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+// The tag that marks a term as compressed.
+const COMPRESSED_TAG: u8 = 80;
+
+// The eetf crate can decode compressed terms but not encode them, so we
+// compress the output of its encoder ourselves.
+fn encode_compressed<W>(term: &Term, writer: &mut W, config: &SerializerConfig) -> Result<()>
+where
+    W: io::Write + ?Sized,
+{
+    let io_error = |e: io::Error| Error::EncodeError(e.to_string());
+
+    let mut encoded = Vec::new();
+    encode(term, &mut encoded, config)?;
+    // The version byte comes before the compressed data, not within it.
+    let (version, data) = encoded.split_at(1);
+    let size = match u32::try_from(data.len()) {
+        Ok(size) => size,
+        Err(_) => return Err(Error::EncodeError("term is too large to compress".into())),
+    };
+
+    let mut encoder = zlib::Encoder::new(Vec::new()).map_err(io_error)?;
+    encoder.write_all(data).map_err(io_error)?;
+    let compressed = encoder.finish().into_result().map_err(io_error)?;
+
+    writer.write_all(version).map_err(io_error)?;
+    writer.write_all(&[COMPRESSED_TAG]).map_err(io_error)?;
+    writer.write_all(&size.to_be_bytes()).map_err(io_error)?;
+    writer.write_all(&compressed).map_err(io_error)?;
+    Ok(())
+}
+
+// Encodes a term, including the version byte.
+fn encode<W>(term: &Term, writer: &mut W, config: &SerializerConfig) -> Result<()>
+where
+    W: io::Write + ?Sized,
+{
+    match config.atom_encoding {
+        AtomEncoding::Utf8 => term.encode(writer)?,
+        AtomEncoding::Latin1 => {
+            let mut encoded = vec![VERSION];
+            encode_latin1(term, &mut encoded)?;
+            writer
+                .write_all(&encoded)
+                .map_err(|e| Error::EncodeError(e.to_string()))?;
+        }
+    }
+    Ok(())
+}
+
+pub(crate) const VERSION: u8 = 131;
+
+const ATOM_EXT: u8 = 100;
+const PORT_EXT: u8 = 102;
+const PID_EXT: u8 = 103;
+const SMALL_TUPLE_EXT: u8 = 104;
+const LARGE_TUPLE_EXT: u8 = 105;
+const NIL_EXT: u8 = 106;
+const LIST_EXT: u8 = 108;
+const NEW_REFERENCE_EXT: u8 = 114;
+const SMALL_ATOM_EXT: u8 = 115;
+const MAP_EXT: u8 = 116;
+
+// The eetf crate always encodes non-ASCII atoms as UTF-8, so we encode terms
+// that can contain atoms ourselves, & leave anything else to eetf.  That
+// includes the node atoms in pids, ports & references, but not the module &
+// function atoms in funs, which are still left to eetf.
+fn encode_latin1(term: &Term, out: &mut Vec<u8>) -> Result<()> {
+    match term {
+        Term::Atom(atom) => encode_latin1_atom(&atom.name, out)?,
+        Term::List(list) if list.elements.is_empty() => out.push(NIL_EXT),
+        Term::List(list) => {
+            out.push(LIST_EXT);
+            encode_length(list.elements.len(), out)?;
+            for element in &list.elements {
+                encode_latin1(element, out)?;
+            }
+            out.push(NIL_EXT);
+        }
+        Term::ImproperList(list) => {
+            out.push(LIST_EXT);
+            encode_length(list.elements.len(), out)?;
+            for element in &list.elements {
+                encode_latin1(element, out)?;
+            }
+            encode_latin1(&list.last, out)?;
+        }
+        Term::Tuple(tuple) => {
+            match u8::try_from(tuple.elements.len()) {
+                Ok(len) => {
+                    out.push(SMALL_TUPLE_EXT);
+                    out.push(len);
+                }
+                Err(_) => {
+                    out.push(LARGE_TUPLE_EXT);
+                    encode_length(tuple.elements.len(), out)?;
+                }
+            }
+            for element in &tuple.elements {
+                encode_latin1(element, out)?;
+            }
+        }
+        Term::Map(map) => {
+            out.push(MAP_EXT);
+            encode_length(map.entries.len(), out)?;
+            for (key, value) in &map.entries {
+                encode_latin1(key, out)?;
+                encode_latin1(value, out)?;
+            }
+        }
+        Term::Pid(pid) => {
+            out.push(PID_EXT);
+            encode_latin1_atom(&pid.node.name, out)?;
+            out.extend_from_slice(&pid.id.to_be_bytes());
+            out.extend_from_slice(&pid.serial.to_be_bytes());
+            out.push(pid.creation);
+        }
+        Term::Port(port) => {
+            out.push(PORT_EXT);
+            encode_latin1_atom(&port.node.name, out)?;
+            out.extend_from_slice(&port.id.to_be_bytes());
+            out.push(port.creation);
+        }
+        Term::Reference(reference) => {
+            let len = u16::try_from(reference.id.len())
+                .map_err(|_| Error::EncodeError("reference id is too long".into()))?;
+            out.push(NEW_REFERENCE_EXT);
+            out.extend_from_slice(&len.to_be_bytes());
+            encode_latin1_atom(&reference.node.name, out)?;
+            out.push(reference.creation);
+            for id in &reference.id {
+                out.extend_from_slice(&id.to_be_bytes());
+            }
+        }
+        other => {
+            let mut encoded = Vec::new();
+            other.encode(&mut encoded)?;
+            // Skip eetf's version byte.
+            out.extend_from_slice(&encoded[1..]);
+        }
+    }
+    Ok(())
+}
+
+fn encode_latin1_atom(name: &str, out: &mut Vec<u8>) -> Result<()> {
+    let bytes = name
+        .chars()
+        .map(|c| u8::try_from(u32::from(c)))
+        .collect::<std::result::Result<Vec<u8>, _>>()
+        .map_err(|_| Error::EncodeError(format!("atom `{}` isn't valid Latin-1", name)))?;
+    match u8::try_from(bytes.len()) {
+        Ok(len) => {
+            out.push(SMALL_ATOM_EXT);
+            out.push(len);
+        }
+        Err(_) => match u16::try_from(bytes.len()) {
+            Ok(len) => {
+                out.push(ATOM_EXT);
+                out.extend_from_slice(&len.to_be_bytes());
+            }
+            Err(_) => return Err(Error::EncodeError(format!("atom `{}` is too long", name))),
+        },
+    }
+    out.extend_from_slice(&bytes);
+    Ok(())
+}
+
+fn encode_length(len: usize, out: &mut Vec<u8>) -> Result<()> {
+    match u32::try_from(len) {
+        Ok(len) => {
+            out.extend_from_slice(&len.to_be_bytes());
+            Ok(())
+        }
+        Err(_) => Err(Error::EncodeError("term has too many elements".into())),
+    }
+}
+
+/// Serializes a value into EETF in a Vec of bytes, with the given config.
+pub fn to_bytes_with_config<T>(value: &T, config: &SerializerConfig) -> Result<Vec<u8>>
 where
     T: Serialize + ?Sized,
 {
     let mut cursor = io::Cursor::new(Vec::new());
 
-    match to_writer(value, &mut cursor) {
+    match to_writer_with_config(value, &mut cursor, config) {
         Ok(_) => Ok(cursor.into_inner()),
         Err(e) => Err(e),
     }
 }
 
-/// Serializes
-struct Serializer {}
+/// Serializes a value into an `eetf::Term`.
+///
+/// Useful for embedding a value in a larger term, or inspecting the term
+/// before it's encoded.
+pub fn to_term<T>(value: &T) -> Result<Term>
+where
+    T: Serialize + ?Sized,
+{
+    to_term_with_config(value, &SerializerConfig::default())
+}
+
+fn to_term_with_config<T>(value: &T, config: &SerializerConfig) -> Result<Term>
+where
+    T: Serialize + ?Sized,
+{
+    let serializer = Serializer {
+        config: config.clone(),
+    };
+    value.serialize(&serializer)
+}
+
+/// Checks whether two values serialize to the same EETF.
+///
+/// This is intended for testing interop, e.g. that a struct produces the same
+/// term as the map some Erlang code expects.  Maps are compared regardless of
+/// the order of their entries, as they would be in Erlang.
+pub fn eetf_eq<A, B>(a: &A, b: &B) -> Result<bool>
+where
+    A: Serialize + ?Sized,
+    B: Serialize + ?Sized,
+{
+    Ok(terms_eq(&to_term(a)?, &to_term(b)?))
+}
+
+fn terms_eq(a: &Term, b: &Term) -> bool {
+    let all_eq =
+        |a: &[Term], b: &[Term]| a.len() == b.len() && a.iter().zip(b).all(|(a, b)| terms_eq(a, b));
+    match (a, b) {
+        (Term::List(a), Term::List(b)) => all_eq(&a.elements, &b.elements),
+        (Term::Tuple(a), Term::Tuple(b)) => all_eq(&a.elements, &b.elements),
+        (Term::ImproperList(a), Term::ImproperList(b)) => {
+            all_eq(&a.elements, &b.elements) && terms_eq(&a.last, &b.last)
+        }
+        (Term::Map(a), Term::Map(b)) => {
+            a.entries.len() == b.entries.len()
+                && a.entries.iter().all(|(key, value)| {
+                    b.entries.iter().any(|(other_key, other_value)| {
+                        terms_eq(key, other_key) && terms_eq(value, other_value)
+                    })
+                })
+        }
+        (a, b) => a == b,
+    }
+}
+
+// Orders terms the way Erlang does: numbers < atoms < references < funs <
+// ports < pids < tuples < maps < [] < lists < binaries.  Terms that Erlang
+// would consider equal, e.g. 1 & 1.0, are ordered by their types.
+fn term_cmp(a: &Term, b: &Term) -> Ordering {
+    fn elements_cmp(a: &[Term], b: &[Term]) -> Ordering {
+        a.iter()
+            .zip(b)
+            .map(|(a, b)| term_cmp(a, b))
+            .find(|&ordering| ordering != Ordering::Equal)
+            .unwrap_or_else(|| a.len().cmp(&b.len()))
+    }
+
+    let by_value = match (a, b) {
+        (Term::FixInteger(a), Term::FixInteger(b)) => a.value.cmp(&b.value),
+        (Term::FixInteger(a), Term::BigInteger(b)) => BigInt::from(a.value).cmp(&b.value),
+        (Term::BigInteger(a), Term::FixInteger(b)) => a.value.cmp(&BigInt::from(b.value)),
+        (Term::BigInteger(a), Term::BigInteger(b)) => a.value.cmp(&b.value),
+        (a, b) if term_rank(a) == 0 && term_rank(b) == 0 => {
+            // At least one of these is a float.
+            let as_f64 = |term: &Term| match term {
+                Term::FixInteger(int) => f64::from(int.value),
+                Term::BigInteger(int) => int.value.to_f64().unwrap_or(0.0),
+                Term::Float(float) => float.value,
+                _ => 0.0,
+            };
+            as_f64(a).partial_cmp(&as_f64(b)).unwrap_or(Ordering::Equal)
+        }
+        (Term::Atom(a), Term::Atom(b)) => a.name.cmp(&b.name),
+        (Term::Tuple(a), Term::Tuple(b)) => a
+            .elements
+            .len()
+            .cmp(&b.elements.len())
+            .then_with(|| elements_cmp(&a.elements, &b.elements)),
+        (Term::List(a), Term::List(b)) => elements_cmp(&a.elements, &b.elements),
+        (Term::Binary(a), Term::Binary(b)) => a.bytes.cmp(&b.bytes),
+        (a, b) if term_rank(a) == term_rank(b) => a.to_string().cmp(&b.to_string()),
+        (a, b) => term_rank(a).cmp(&term_rank(b)),
+    };
+    by_value.then_with(|| term_type(a).cmp(&term_type(b)))
+}
+
+// The position of a term's type in Erlang's term order.
+fn term_rank(term: &Term) -> u8 {
+    match term {
+        Term::FixInteger(_) | Term::BigInteger(_) | Term::Float(_) => 0,
+        Term::Atom(_) => 1,
+        Term::Reference(_) => 2,
+        Term::ExternalFun(_) | Term::InternalFun(_) => 3,
+        Term::Port(_) => 4,
+        Term::Pid(_) => 5,
+        Term::Tuple(_) => 6,
+        Term::Map(_) => 7,
+        Term::List(list) if list.elements.is_empty() => 8,
+        Term::List(_) | Term::ImproperList(_) => 9,
+        Term::Binary(_) | Term::BitBinary(_) => 10,
+    }
+}
+
+// Breaks ties between terms that Erlang considers equal, so that sorting is
+// deterministic.
+fn term_type(term: &Term) -> u8 {
+    match term {
+        Term::FixInteger(_) | Term::BigInteger(_) => 0,
+        Term::Float(_) => 1,
+        Term::List(_) => 2,
+        Term::ImproperList(_) => 3,
+        Term::Binary(_) => 4,
+        Term::BitBinary(_) => 5,
+        _ => 6,
+    }
+}
+
+/// Counts the distinct atoms that serializing a value would produce.
+///
+/// The BEAM never garbage collects atoms, and crashes if its atom table fills
+/// up.  This can be used to check that a value, e.g. a map with atom keys
+/// built from user data, stays within some budget before it's sent.
+pub fn count_distinct_atoms<T>(value: &T) -> Result<usize>
+where
+    T: Serialize + ?Sized,
+{
+    count_distinct_atoms_with_config(value, &SerializerConfig::default())
+}
+
+/// Counts the distinct atoms that serializing a value with the given config
+/// would produce.
+pub fn count_distinct_atoms_with_config<T>(value: &T, config: &SerializerConfig) -> Result<usize>
+where
+    T: Serialize + ?Sized,
+{
+    let term = to_term_with_config(value, config)?;
+    let mut atoms = HashSet::new();
+    collect_atoms(&term, &mut atoms);
+    Ok(atoms.len())
+}
+
+fn collect_atoms<'a>(term: &'a Term, atoms: &mut HashSet<&'a str>) {
+    match term {
+        Term::Atom(atom) => {
+            atoms.insert(&atom.name);
+        }
+        Term::Pid(pid) => {
+            atoms.insert(&pid.node.name);
+        }
+        Term::Port(port) => {
+            atoms.insert(&port.node.name);
+        }
+        Term::Reference(reference) => {
+            atoms.insert(&reference.node.name);
+        }
+        Term::List(list) => {
+            for element in &list.elements {
+                collect_atoms(element, atoms);
+            }
+        }
+        Term::ImproperList(list) => {
+            for element in &list.elements {
+                collect_atoms(element, atoms);
+            }
+            collect_atoms(&list.last, atoms);
+        }
+        Term::Tuple(tuple) => {
+            for element in &tuple.elements {
+                collect_atoms(element, atoms);
+            }
+        }
+        Term::Map(map) => {
+            for (key, value) in &map.entries {
+                collect_atoms(key, atoms);
+                collect_atoms(value, atoms);
+            }
+        }
+        _ => (),
+    }
+}
+
+/// Serializes values into EETF with a `SerializerConfig`.
+///
+/// `to_bytes_with_config` & friends are usually more convenient, but
+/// `Serializer::builder` can be easier to read when setting many options:
+///
+/// ```rust
+/// # #[macro_use]
+/// # extern crate serde_derive;
+/// # extern crate serde_eetf;
+/// # fn main() {
+/// use serde_eetf::{KeyFormat, Serializer};
+///
+/// #[derive(Serialize)]
+/// struct Reply {
+///     status: Option<u8>,
+/// }
+///
+/// let bytes = Serializer::builder()
+///     .key_format(KeyFormat::Binary)
+///     .none_atom("undefined")
+///     .compress(true)
+///     .to_bytes(&Reply { status: None })
+///     .unwrap();
+/// # }
+/// ```
+///
+/// `&Serializer` implements `serde::Serializer`, producing an `eetf::Term`.
+#[derive(Clone, Debug)]
+pub struct Serializer {
+    config: SerializerConfig,
+}
+
+impl Serializer {
+    /// Creates a serializer with the given config.
+    pub fn new(config: SerializerConfig) -> Self {
+        Serializer { config }
+    }
+
+    /// Starts building a serializer, with the default config.
+    pub fn builder() -> SerializerBuilder {
+        SerializerBuilder::default()
+    }
+
+    /// Serializes a value into EETF in a Vec of bytes.
+    pub fn to_bytes<T>(&self, value: &T) -> Result<Vec<u8>>
+    where
+        T: Serialize + ?Sized,
+    {
+        to_bytes_with_config(value, &self.config)
+    }
+
+    /// Serializes a value into EETF using a Write.
+    pub fn to_writer<T, W>(&self, value: &T, writer: &mut W) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+        W: io::Write + ?Sized,
+    {
+        to_writer_with_config(value, writer, &self.config)
+    }
+}
+
+/// Builds a `Serializer`, one option at a time.  See `Serializer::builder`.
+#[derive(Clone, Debug, Default)]
+pub struct SerializerBuilder {
+    config: SerializerConfig,
+}
+
+impl SerializerBuilder {
+    /// Sets `SerializerConfig::variant_tag`.
+    pub fn variant_tag(mut self, variant_tag: VariantTag) -> Self {
+        self.config.variant_tag = variant_tag;
+        self
+    }
+
+    /// Sets `SerializerConfig::string_format`.
+    pub fn string_format(mut self, string_format: StringFormat) -> Self {
+        self.config.string_format = string_format;
+        self
+    }
+
+    /// Sets `SerializerConfig::char_format`.
+    pub fn char_format(mut self, char_format: CharFormat) -> Self {
+        self.config.char_format = char_format;
+        self
+    }
+
+    /// Sets `SerializerConfig::bool_format`.
+    pub fn bool_format(mut self, bool_format: BoolFormat) -> Self {
+        self.config.bool_format = bool_format;
+        self
+    }
+
+    /// Sets `SerializerConfig::key_format`.
+    pub fn key_format(mut self, key_format: KeyFormat) -> Self {
+        self.config.key_format = key_format;
+        self
+    }
+
+    /// Sets `SerializerConfig::map_key_format`.
+    pub fn map_key_format(mut self, map_key_format: MapKeyFormat) -> Self {
+        self.config.map_key_format = map_key_format;
+        self
+    }
+
+    /// Sets `SerializerConfig::tuple_struct_format`.
+    pub fn tuple_struct_format(mut self, tuple_struct_format: TupleStructFormat) -> Self {
+        self.config.tuple_struct_format = tuple_struct_format;
+        self
+    }
+
+    /// Sets `SerializerConfig::struct_format`.
+    pub fn struct_format(mut self, struct_format: StructFormat) -> Self {
+        self.config.struct_format = struct_format;
+        self
+    }
+
+    /// Sets `SerializerConfig::newtype_struct_format`.
+    pub fn newtype_struct_format(mut self, newtype_struct_format: NewtypeStructFormat) -> Self {
+        self.config.newtype_struct_format = newtype_struct_format;
+        self
+    }
+
+    /// Sets `SerializerConfig::float_format`.
+    pub fn float_format(mut self, float_format: FloatFormat) -> Self {
+        self.config.float_format = float_format;
+        self
+    }
+
+    /// Sets `SerializerConfig::nan_handling`.
+    pub fn nan_handling(mut self, nan_handling: NanHandling) -> Self {
+        self.config.nan_handling = nan_handling;
+        self
+    }
+
+    /// Sets `SerializerConfig::empty_map_format`.
+    pub fn empty_map_format(mut self, empty_map_format: EmptyMapFormat) -> Self {
+        self.config.empty_map_format = empty_map_format;
+        self
+    }
+
+    /// Sets `SerializerConfig::sort_map_keys`.
+    pub fn sort_map_keys(mut self, sort_map_keys: bool) -> Self {
+        self.config.sort_map_keys = sort_map_keys;
+        self
+    }
+
+    /// Sets `SerializerConfig::variant_format`.
+    pub fn variant_format(mut self, variant_format: VariantFormat) -> Self {
+        self.config.variant_format = variant_format;
+        self
+    }
+
+    /// Sets `SerializerConfig::variant_case`.
+    pub fn variant_case(mut self, variant_case: VariantCase) -> Self {
+        self.config.variant_case = variant_case;
+        self
+    }
+
+    /// Sets `SerializerConfig::compress`.
+    pub fn compress(mut self, compress: bool) -> Self {
+        self.config.compress = compress;
+        self
+    }
+
+    /// Sets `SerializerConfig::none_atom`.
+    pub fn none_atom<S: Into<String>>(mut self, none_atom: S) -> Self {
+        self.config.none_atom = none_atom.into();
+        self
+    }
+
+    /// Sets `SerializerConfig::atom_encoding`.
+    pub fn atom_encoding(mut self, atom_encoding: AtomEncoding) -> Self {
+        self.config.atom_encoding = atom_encoding;
+        self
+    }
+
+    /// Sets `SerializerConfig::unit_format`.
+    pub fn unit_format(mut self, unit_format: UnitFormat) -> Self {
+        self.config.unit_format = unit_format;
+        self
+    }
+
+    /// Builds the serializer.
+    pub fn build(self) -> Serializer {
+        Serializer::new(self.config)
+    }
+
+    /// Serializes a value into EETF in a Vec of bytes.
+    pub fn to_bytes<T>(&self, value: &T) -> Result<Vec<u8>>
+    where
+        T: Serialize + ?Sized,
+    {
+        to_bytes_with_config(value, &self.config)
+    }
+
+    /// Serializes a value into EETF using a Write.
+    pub fn to_writer<T, W>(&self, value: &T, writer: &mut W) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+        W: io::Write + ?Sized,
+    {
+        to_writer_with_config(value, writer, &self.config)
+    }
+}
+
+impl Serializer {
+    // Converts the name of an enum variant into the name of its tag.
+    fn variant_name(&self, variant: &'static str) -> String {
+        match self.config.variant_case {
+            VariantCase::SnakeCase => variant.to_snake_case(),
+            VariantCase::AsIs => variant.to_string(),
+        }
+    }
+
+    // Builds the term that tags a tuple with the name of an enum variant.
+    fn variant_tag(&self, variant: &'static str) -> Term {
+        let name = self.variant_name(variant);
+        match self.config.variant_tag {
+            VariantTag::Atom => Term::Atom(eetf::Atom::from(name)),
+            VariantTag::Binary => Term::Binary(eetf::Binary::from(name.as_bytes())),
+        }
+    }
+
+    // Builds the map key for a field of a struct or struct variant.  Unlike
+    // variant names, field names aren't converted to snake_case.
+    fn field_key(&self, key: &'static str) -> Term {
+        match self.config.key_format {
+            KeyFormat::Atom => Term::Atom(eetf::Atom::from(key)),
+            KeyFormat::Binary => Term::Binary(eetf::Binary::from(key.as_bytes())),
+        }
+    }
+
+    // Serializes the string inside an Atom.  It's serialized as a binary, no
+    // matter the string_format, and then converted.  The config is only
+    // copied when it has to be changed, as atoms are common in large terms.
+    fn atom<T>(&self, value: &T) -> Result<Term>
+    where
+        T: ?Sized + Serialize,
+    {
+        let serialized = if self.config.string_format == StringFormat::Binary {
+            value.serialize(self)?
+        } else {
+            let serializer = Serializer {
+                config: SerializerConfig {
+                    string_format: StringFormat::Binary,
+                    ..self.config.clone()
+                },
+            };
+            value.serialize(&serializer)?
+        };
+        match serialized {
+            Term::Binary(binary) => match str::from_utf8(&binary.bytes) {
+                Ok(name) => Ok(Term::Atom(eetf::Atom::from(name))),
+                Err(_) => Err(Error::Utf8DecodeError),
+            },
+            _ => Err(Error::ExpectedBinary),
+        }
+    }
+
+    // Applies the map_key_format to an already serialized map key.  Only
+    // binaries holding UTF-8 are considered to be strings.
+    fn map_key(&self, key: Term) -> Term {
+        let name = match (self.config.map_key_format, &key) {
+            (MapKeyFormat::Binary, _) => None,
+            (_, Term::Binary(binary)) => str::from_utf8(&binary.bytes).ok(),
+            _ => None,
+        };
+        match name {
+            Some(name)
+                if self.config.map_key_format == MapKeyFormat::Atom || is_safe_atom(name) =>
+            {
+                Term::Atom(eetf::Atom::from(name))
+            }
+            _ => key,
+        }
+    }
+}
+
+// The tag Rust's Result gives `variant`, if `name` is Result.  The
+// deserializer checks for Result with this too, so that other enums with
+// `Ok` & `Err` variants are tagged & found like any other.
+pub(crate) fn result_tag(name: &str, variant: &str) -> Option<&'static str> {
+    match (name, variant) {
+        ("Result", "Ok") => Some("ok"),
+        ("Result", "Err") => Some("error"),
+        _ => None,
+    }
+}
+
+// Checks whether a string matches `[a-z][a-z0-9_]*`.
+fn is_safe_atom(name: &str) -> bool {
+    let mut bytes = name.bytes();
+    match bytes.next() {
+        Some(b'a'..=b'z') => {
+            bytes.all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'_')
+        }
+        _ => false,
+    }
+}
 
-struct SequenceSerializer {
+// The compound serializers keep a reference to their parent Serializer, so
+// that any config applies to the values nested inside them.
+struct SequenceSerializer<'a> {
+    serializer: &'a Serializer,
     items: Vec<Term>,
 }
 
-struct NamedSequenceSerializer {
+struct NamedSequenceSerializer<'a> {
+    serializer: &'a Serializer,
     name: Term,
     items: Vec<Term>,
 }
 
-struct MapSerializer {
+struct MapSerializer<'a> {
+    serializer: &'a Serializer,
     items: Vec<(Term, Term)>,
+    // A key passed to serialize_key, waiting for its value.
+    key: Option<Term>,
 }
 
-struct NamedMapSerializer {
-    name: Term,
+struct NamedMapSerializer<'a> {
+    serializer: &'a Serializer,
+    // The name of the struct or variant.  It's only turned into a tag if the
+    // format needs one.
+    name: &'static str,
     items: Vec<(Term, Term)>,
 }
 
@@ -68,23 +857,25 @@ impl<'a> ser::Serializer for &'a Serializer {
 
     // Associated types for keeping track of additional state while serializing
     // compound data structures like sequences and maps.
-    type SerializeSeq = SequenceSerializer;
-    type SerializeTuple = SequenceSerializer;
-    type SerializeTupleStruct = SequenceSerializer;
-    type SerializeTupleVariant = NamedSequenceSerializer;
-    type SerializeMap = MapSerializer;
-    type SerializeStruct = MapSerializer;
-    type SerializeStructVariant = NamedMapSerializer;
+    type SerializeSeq = SequenceSerializer<'a>;
+    type SerializeTuple = SequenceSerializer<'a>;
+    type SerializeTupleStruct = SequenceSerializer<'a>;
+    type SerializeTupleVariant = NamedSequenceSerializer<'a>;
+    type SerializeMap = MapSerializer<'a>;
+    type SerializeStruct = NamedMapSerializer<'a>;
+    type SerializeStructVariant = NamedMapSerializer<'a>;
 
     // The following 12 methods receive one of the primitive types of the data
     // model and map it to eetf
     fn serialize_bool(self, v: bool) -> Result<Term> {
-        // TODO: Make this actually a boolean?
-        Ok(Term::Atom(eetf::Atom::from(if v {
-            "true"
-        } else {
-            "false"
-        })))
+        match self.config.bool_format {
+            BoolFormat::Atom => Ok(Term::Atom(eetf::Atom::from(if v {
+                "true"
+            } else {
+                "false"
+            }))),
+            BoolFormat::Integer => self.serialize_u8(if v { 1 } else { 0 }),
+        }
     }
 
     // eetf has two kinds of integers: 32 bit ones and big ints.
@@ -100,9 +891,21 @@ impl<'a> ser::Serializer for &'a Serializer {
         Ok(Term::FixInteger(eetf::FixInteger { value: v }))
     }
 
+    // Values that fit into an i32 are encoded as a FixInteger, the rest as a
+    // BigInteger.
     fn serialize_i64(self, v: i64) -> Result<Term> {
-        let big_int = BigInt::from_i64(v).expect("TODO: Handle failure here");
-        Ok(Term::BigInteger(eetf::BigInteger { value: big_int }))
+        match i32::try_from(v) {
+            Ok(v) => self.serialize_i32(v),
+            Err(_) => Ok(Term::BigInteger(eetf::BigInteger {
+                value: BigInt::from(v),
+            })),
+        }
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<Term> {
+        Ok(Term::BigInteger(eetf::BigInteger {
+            value: BigInt::from(v),
+        }))
     }
 
     fn serialize_u8(self, v: u8) -> Result<Term> {
@@ -113,15 +916,25 @@ impl<'a> ser::Serializer for &'a Serializer {
         Ok(Term::FixInteger(eetf::FixInteger::from(v)))
     }
 
-    // The eetf crate uses an i32 to encode FixIntegers, so for unsigned numbers
-    // we use a BigInteger instead.
+    // The eetf crate uses an i32 to encode FixIntegers, so larger unsigned
+    // numbers need a BigInteger.
     fn serialize_u32(self, v: u32) -> Result<Term> {
         self.serialize_u64(u64::from(v))
     }
 
     fn serialize_u64(self, v: u64) -> Result<Term> {
-        let big_int = BigInt::from_u64(v).expect("TODO: Handle failure here");
-        Ok(Term::BigInteger(eetf::BigInteger { value: big_int }))
+        match i32::try_from(v) {
+            Ok(v) => self.serialize_i32(v),
+            Err(_) => Ok(Term::BigInteger(eetf::BigInteger {
+                value: BigInt::from(v),
+            })),
+        }
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<Term> {
+        Ok(Term::BigInteger(eetf::BigInteger {
+            value: BigInt::from(v),
+        }))
     }
 
     fn serialize_f32(self, v: f32) -> Result<Term> {
@@ -129,26 +942,74 @@ impl<'a> ser::Serializer for &'a Serializer {
     }
 
     fn serialize_f64(self, v: f64) -> Result<Term> {
-        Ok(Term::Float(eetf::Float::try_from(v)?))
+        if !v.is_finite() && self.config.nan_handling == NanHandling::Atom {
+            let name = if v.is_nan() {
+                "nan"
+            } else if v > 0.0 {
+                "infinity"
+            } else {
+                "neg_infinity"
+            };
+            return Ok(Term::Atom(eetf::Atom::from(name)));
+        }
+        match self.config.float_format {
+            FloatFormat::Float => Ok(Term::Float(eetf::Float::try_from(v)?)),
+            FloatFormat::String => {
+                // Both of these give the fewest digits that round trip, but
+                // which is shorter depends on the magnitude of the float.
+                let plain = v.to_string();
+                let exponent = format!("{:e}", v);
+                let mut shortest = if exponent.len() < plain.len() {
+                    exponent
+                } else {
+                    plain
+                };
+                // binary_to_float/1 needs a decimal point in the mantissa,
+                // e.g. 1.0 rather than 1 & 1.0e308 rather than 1e308.
+                let mantissa_end = shortest.find('e').unwrap_or_else(|| shortest.len());
+                if !shortest[..mantissa_end].contains('.') {
+                    shortest.insert_str(mantissa_end, ".0");
+                }
+                self.serialize_str(&shortest)
+            }
+        }
     }
 
-    // Serialize a char as a single-character string.
-    // TODO: Decide if this is a good idea.
+    // Serialize a char as a single-character string, or as an integer if we're
+    // using charlists, so that a String and a Vec<char> end up the same.  The
+    // char_format can ask for integers regardless.
     fn serialize_char(self, v: char) -> Result<Term> {
-        self.serialize_str(&v.to_string())
+        match (self.config.char_format, self.config.string_format) {
+            (CharFormat::String, StringFormat::Binary) => self.serialize_str(&v.to_string()),
+            (CharFormat::String, StringFormat::CharList) | (CharFormat::Integer, _) => {
+                Ok(Term::FixInteger(eetf::FixInteger::from(v as i32)))
+            }
+        }
     }
 
     fn serialize_str(self, v: &str) -> Result<Term> {
-        Ok(Term::Binary(eetf::Binary::from(v.as_bytes())))
+        match self.config.string_format {
+            StringFormat::Binary => Ok(Term::Binary(eetf::Binary::from(v.as_bytes()))),
+            StringFormat::CharList => Ok(Term::List(eetf::List::from(
+                v.chars()
+                    .map(|c| Term::FixInteger(eetf::FixInteger::from(c as i32)))
+                    .collect::<Vec<_>>(),
+            ))),
+        }
     }
 
+    // This copies the bytes, as serde only lends us them while eetf::Binary
+    // needs to own a Vec.  There's no way for a Serialize impl to hand over an
+    // owned buffer, so avoiding the copy would mean encoding straight to the
+    // writer rather than building a Term first.
     fn serialize_bytes(self, v: &[u8]) -> Result<Term> {
         Ok(Term::Binary(eetf::Binary::from(v)))
     }
 
-    // An absent optional is represented as the JSON `null`.
+    // An absent optional is represented as the configured atom, `nil` by
+    // default.
     fn serialize_none(self) -> Result<Term> {
-        Ok(Term::Atom(eetf::Atom::from("nil")))
+        Ok(Term::Atom(eetf::Atom::from(self.config.none_atom.as_str())))
     }
 
     // At present optional is represented as just the contained value. Note that
@@ -163,10 +1024,13 @@ impl<'a> ser::Serializer for &'a Serializer {
     }
 
     // In Serde, unit means an anonymous value containing no data.
-    // Map this to eetf as `nil`.
+    // Map this to eetf the same as `None`.
     fn serialize_unit(self) -> Result<Term> {
-        // TODO: Decide if this is right.
-        self.serialize_none()
+        match self.config.unit_format {
+            UnitFormat::Nil => self.serialize_none(),
+            UnitFormat::EmptyTuple => Ok(Term::Tuple(eetf::Tuple::from(vec![]))),
+            UnitFormat::EmptyList => Ok(Term::List(eetf::List::from(vec![]))),
+        }
     }
 
     // Unit struct means a named value containing no data.
@@ -185,27 +1049,45 @@ impl<'a> ser::Serializer for &'a Serializer {
         _variant_index: u32,
         variant: &'static str,
     ) -> Result<Term> {
-        Ok(Term::Atom(eetf::Atom::from(variant.to_snake_case())))
+        Ok(Term::Atom(eetf::Atom::from(self.variant_name(variant))))
     }
 
-    // We treat newtype structs as insignificant wrappers around the data they
-    // contain.
-    fn serialize_newtype_struct<T>(self, _name: &'static str, value: &T) -> Result<Term>
+    // By default we treat newtype structs as insignificant wrappers around the
+    // data they contain.
+    fn serialize_newtype_struct<T>(self, name: &'static str, value: &T) -> Result<Term>
     where
         T: ?Sized + Serialize,
     {
-        value.serialize(self)
+        if name == atom::TOKEN {
+            return self.atom(value);
+        }
+        if name == value::TOKEN {
+            return match value::take_term() {
+                Some(term) => Ok(term),
+                None => Err(ser::Error::custom("EetfValue didn't pass a term")),
+            };
+        }
+        match self.config.newtype_struct_format {
+            NewtypeStructFormat::Transparent => value.serialize(self),
+            NewtypeStructFormat::Tagged => Ok(Term::Tuple(eetf::Tuple::from(vec![
+                Term::Atom(eetf::Atom::from(name.to_snake_case())),
+                value.serialize(self)?,
+            ]))),
+        }
     }
 
     // Note that newtype variant (and all of the other variant serialization
     // methods) refer exclusively to the "externally tagged" enum
     // representation.
     //
-    // We serialize this to {value_name, value}, which hopefully allows results
-    // to be serialized into fairly standard erlang ok/err tuples.
+    // We serialize this to {value_name, value}.  Rust's Result is special
+    // cased into the standard erlang {ok, Value} & {error, Reason} tuples,
+    // whatever the variant_case.  Serde only tells us the name of the type,
+    // so an enum of your own that's also named Result, with Ok & Err
+    // variants, is tagged the same way.
     fn serialize_newtype_variant<T>(
         self,
-        _name: &'static str,
+        name: &'static str,
         _variant_index: u32,
         variant: &'static str,
         value: &T,
@@ -213,11 +1095,9 @@ impl<'a> ser::Serializer for &'a Serializer {
     where
         T: ?Sized + Serialize,
     {
+        let tag = self.variant_tag(result_tag(name, variant).unwrap_or(variant));
         let serialized_value = value.serialize(self)?;
-        Ok(Term::Tuple(eetf::Tuple::from(vec![
-            Term::Atom(eetf::Atom::from(variant.to_snake_case())),
-            serialized_value,
-        ])))
+        Ok(Term::Tuple(eetf::Tuple::from(vec![tag, serialized_value])))
     }
 
     fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq> {
@@ -225,11 +1105,15 @@ impl<'a> ser::Serializer for &'a Serializer {
             None => Vec::new(),
             Some(len) => Vec::with_capacity(len),
         };
-        Ok(SequenceSerializer { items: vec })
+        Ok(SequenceSerializer {
+            serializer: self,
+            items: vec,
+        })
     }
 
     fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple> {
         Ok(SequenceSerializer {
+            serializer: self,
             items: Vec::with_capacity(len),
         })
     }
@@ -238,15 +1122,25 @@ impl<'a> ser::Serializer for &'a Serializer {
     // TODO: Decide if this is a good idea.
     fn serialize_tuple_struct(
         self,
-        _name: &'static str,
+        name: &'static str,
         len: usize,
     ) -> Result<Self::SerializeTupleStruct> {
-        self.serialize_tuple(len)
+        match self.config.tuple_struct_format {
+            TupleStructFormat::Tuple => self.serialize_tuple(len),
+            TupleStructFormat::Record => {
+                let mut items = Vec::with_capacity(len + 1);
+                items.push(Term::Atom(eetf::Atom::from(name.to_snake_case())));
+                Ok(SequenceSerializer {
+                    serializer: self,
+                    items,
+                })
+            }
+        }
     }
 
-    // Tuple variants are represented in eetf as `{name, {data}}`. Again
-    // this method is only responsible for the externally tagged representation.
-    // TODO: decide if this is a good idea...
+    // Tuple variants are represented in eetf as `{name, {data}}`, or as
+    // `{name, data...}` with VariantFormat::FlatTuple.  Again this method is
+    // only responsible for the externally tagged representation.
     fn serialize_tuple_variant(
         self,
         _name: &'static str,
@@ -255,7 +1149,8 @@ impl<'a> ser::Serializer for &'a Serializer {
         len: usize,
     ) -> Result<Self::SerializeTupleVariant> {
         Ok(NamedSequenceSerializer {
-            name: Term::Atom(eetf::Atom::from(variant.to_snake_case())),
+            serializer: self,
+            name: self.variant_tag(variant),
             items: Vec::with_capacity(len),
         })
     }
@@ -265,13 +1160,20 @@ impl<'a> ser::Serializer for &'a Serializer {
             None => Vec::new(),
             Some(len) => Vec::with_capacity(len),
         };
-        Ok(MapSerializer { items: vec })
+        Ok(MapSerializer {
+            serializer: self,
+            items: vec,
+            key: None,
+        })
     }
 
-    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<Self::SerializeStruct> {
-        // TODO: decide how to do this....
-        // do we want to tag things?
-        self.serialize_map(Some(len))
+    // Structs are maps by default, but can be records.
+    fn serialize_struct(self, name: &'static str, len: usize) -> Result<Self::SerializeStruct> {
+        Ok(NamedMapSerializer {
+            serializer: self,
+            name,
+            items: Vec::with_capacity(len),
+        })
     }
 
     fn serialize_struct_variant(
@@ -282,13 +1184,14 @@ impl<'a> ser::Serializer for &'a Serializer {
         len: usize,
     ) -> Result<Self::SerializeStructVariant> {
         Ok(NamedMapSerializer {
-            name: Term::Atom(eetf::Atom::from(variant.to_snake_case())),
+            serializer: self,
+            name: variant,
             items: Vec::with_capacity(len),
         })
     }
 }
 
-impl<'a> ser::SerializeSeq for SequenceSerializer {
+impl<'a> ser::SerializeSeq for SequenceSerializer<'a> {
     type Ok = Term;
     type Error = Error;
 
@@ -297,7 +1200,7 @@ impl<'a> ser::SerializeSeq for SequenceSerializer {
     where
         T: ?Sized + Serialize,
     {
-        let term_value = value.serialize(&Serializer {})?;
+        let term_value = value.serialize(self.serializer)?;
         self.items.push(term_value);
         Ok(())
     }
@@ -309,7 +1212,7 @@ impl<'a> ser::SerializeSeq for SequenceSerializer {
     }
 }
 
-impl<'a> ser::SerializeTuple for SequenceSerializer {
+impl<'a> ser::SerializeTuple for SequenceSerializer<'a> {
     type Ok = Term;
     type Error = Error;
 
@@ -318,7 +1221,7 @@ impl<'a> ser::SerializeTuple for SequenceSerializer {
     where
         T: ?Sized + Serialize,
     {
-        let term_value = value.serialize(&Serializer {})?;
+        let term_value = value.serialize(self.serializer)?;
         self.items.push(term_value);
         Ok(())
     }
@@ -330,7 +1233,7 @@ impl<'a> ser::SerializeTuple for SequenceSerializer {
     }
 }
 
-impl<'a> ser::SerializeTupleStruct for SequenceSerializer {
+impl<'a> ser::SerializeTupleStruct for SequenceSerializer<'a> {
     type Ok = Term;
     type Error = Error;
 
@@ -339,7 +1242,7 @@ impl<'a> ser::SerializeTupleStruct for SequenceSerializer {
     where
         T: ?Sized + Serialize,
     {
-        let term_value = value.serialize(&Serializer {})?;
+        let term_value = value.serialize(self.serializer)?;
         self.items.push(term_value);
         Ok(())
     }
@@ -351,7 +1254,7 @@ impl<'a> ser::SerializeTupleStruct for SequenceSerializer {
     }
 }
 
-impl<'a> ser::SerializeTupleVariant for NamedSequenceSerializer {
+impl<'a> ser::SerializeTupleVariant for NamedSequenceSerializer<'a> {
     type Ok = Term;
     type Error = Error;
 
@@ -360,12 +1263,16 @@ impl<'a> ser::SerializeTupleVariant for NamedSequenceSerializer {
     where
         T: ?Sized + Serialize,
     {
-        let term_value = value.serialize(&Serializer {})?;
+        let term_value = value.serialize(self.serializer)?;
         self.items.push(term_value);
         Ok(())
     }
 
     fn end(self) -> Result<Term> {
+        if self.serializer.config.variant_format == VariantFormat::FlatTuple {
+            let elements = tagged(self.name, self.items.into_iter());
+            return Ok(Term::Tuple(eetf::Tuple::from(elements)));
+        }
         // TODO: rename items to elements.
         let serialized_data = Term::Tuple(eetf::Tuple {
             elements: self.items,
@@ -377,45 +1284,71 @@ impl<'a> ser::SerializeTupleVariant for NamedSequenceSerializer {
     }
 }
 
-impl<'a> ser::SerializeMap for MapSerializer {
+impl<'a> ser::SerializeMap for MapSerializer<'a> {
     type Ok = Term;
     type Error = Error;
 
-    // Serialize a single element of the sequence.
-    fn serialize_key<T>(&mut self, _value: &T) -> Result<()>
+    // Most types use serialize_entry, but some serialize keys & values
+    // separately.
+    fn serialize_key<T>(&mut self, key: &T) -> Result<()>
     where
         T: ?Sized + Serialize,
     {
-        panic!("Not Implemented")
+        self.key = Some(self.serializer.map_key(key.serialize(self.serializer)?));
+        Ok(())
     }
 
-    fn serialize_value<T>(&mut self, _value: &T) -> Result<()>
+    fn serialize_value<T>(&mut self, value: &T) -> Result<()>
     where
         T: ?Sized + Serialize,
     {
-        panic!("Not Implemented")
+        match self.key.take() {
+            Some(key_term) => {
+                let value_term = value.serialize(self.serializer)?;
+                self.items.push((key_term, value_term));
+                Ok(())
+            }
+            None => Err(ser::Error::custom(
+                "serialize_value was called before serialize_key",
+            )),
+        }
     }
 
+    // Erlang maps can be keyed by any term, so keys are serialized just like
+    // any other value: strings become binaries, integers become integers,
+    // tuples become tuples etc.
     fn serialize_entry<K: ?Sized, V: ?Sized>(&mut self, key: &K, value: &V) -> Result<()>
     where
         K: Serialize,
         V: Serialize,
     {
-        let key_term = key.serialize(&Serializer {})?;
-        let value_term = value.serialize(&Serializer {})?;
+        let key_term = self.serializer.map_key(key.serialize(self.serializer)?);
+        let value_term = value.serialize(self.serializer)?;
         self.items.push((key_term, value_term));
         Ok(())
     }
 
     fn end(self) -> Result<Term> {
+        if self.items.is_empty() {
+            match self.serializer.config.empty_map_format {
+                EmptyMapFormat::Map => (),
+                EmptyMapFormat::Nil => {
+                    let none_atom = self.serializer.config.none_atom.as_str();
+                    return Ok(Term::Atom(eetf::Atom::from(none_atom)));
+                }
+                EmptyMapFormat::List => return Ok(Term::List(eetf::List::from(vec![]))),
+            }
+        }
+        let mut items = self.items;
+        if self.serializer.config.sort_map_keys {
+            items.sort_by(|(a, _), (b, _)| term_cmp(a, b));
+        }
         // TODO: rename items to entries.
-        Ok(Term::Map(eetf::Map {
-            entries: self.items,
-        }))
+        Ok(Term::Map(eetf::Map { entries: items }))
     }
 }
 
-impl<'a> ser::SerializeStruct for MapSerializer {
+impl<'a> ser::SerializeStruct for NamedMapSerializer<'a> {
     type Ok = Term;
     type Error = Error;
 
@@ -423,20 +1356,36 @@ impl<'a> ser::SerializeStruct for MapSerializer {
     where
         T: ?Sized + Serialize,
     {
-        let value_term = value.serialize(&Serializer {})?;
+        let value_term = value.serialize(self.serializer)?;
         self.items
-            .push((Term::Atom(eetf::Atom::from(key)), value_term));
+            .push((self.serializer.field_key(key), value_term));
         Ok(())
     }
 
+    // Records are matched up with fields by position, so leaving one out
+    // would shift the rest.
+    fn skip_field(&mut self, key: &'static str) -> Result<()> {
+        match self.serializer.config.struct_format {
+            StructFormat::Map => Ok(()),
+            StructFormat::Record => Err(skipped_field(key)),
+        }
+    }
+
     fn end(self) -> Result<Term> {
-        Ok(Term::Map(eetf::Map {
-            entries: self.items,
-        }))
+        match self.serializer.config.struct_format {
+            StructFormat::Map => Ok(Term::Map(eetf::Map {
+                entries: self.items,
+            })),
+            StructFormat::Record => {
+                let tag = Term::Atom(eetf::Atom::from(self.name.to_snake_case()));
+                let elements = tagged(tag, self.items.into_iter().map(|(_, value)| value));
+                Ok(Term::Tuple(eetf::Tuple::from(elements)))
+            }
+        }
     }
 }
 
-impl<'a> ser::SerializeStructVariant for NamedMapSerializer {
+impl<'a> ser::SerializeStructVariant for NamedMapSerializer<'a> {
     type Ok = Term;
     type Error = Error;
 
@@ -444,23 +1393,52 @@ impl<'a> ser::SerializeStructVariant for NamedMapSerializer {
     where
         T: ?Sized + Serialize,
     {
-        let value_term = value.serialize(&Serializer {})?;
+        let value_term = value.serialize(self.serializer)?;
         self.items
-            .push((Term::Atom(eetf::Atom::from(key)), value_term));
+            .push((self.serializer.field_key(key), value_term));
+        Ok(())
+    }
+
+    fn skip_field(&mut self, key: &'static str) -> Result<()> {
+        if self.serializer.config.variant_format == VariantFormat::FlatTuple {
+            return Err(skipped_field(key));
+        }
         Ok(())
     }
 
     fn end(self) -> Result<Term> {
+        let tag = self.serializer.variant_tag(self.name);
+        if self.serializer.config.variant_format == VariantFormat::FlatTuple {
+            let elements = tagged(tag, self.items.into_iter().map(|(_, value)| value));
+            return Ok(Term::Tuple(eetf::Tuple::from(elements)));
+        }
         let serialized_data = Term::Map(eetf::Map {
             entries: self.items,
         });
-        Ok(Term::Tuple(eetf::Tuple::from(vec![
-            self.name,
-            serialized_data,
-        ])))
+        Ok(Term::Tuple(eetf::Tuple::from(vec![tag, serialized_data])))
     }
 }
 
+// The error for a field that was skipped when its position matters.
+fn skipped_field(key: &'static str) -> Error {
+    ser::Error::custom(format!(
+        "field `{}` can't be skipped when fields are encoded by position",
+        key
+    ))
+}
+
+// Builds the elements of a tuple that starts with `tag`, allocating them all
+// at once.
+fn tagged<I>(tag: Term, rest: I) -> Vec<Term>
+where
+    I: ExactSizeIterator<Item = Term>,
+{
+    let mut elements = Vec::with_capacity(rest.len() + 1);
+    elements.push(tag);
+    elements.extend(rest);
+    elements
+}
+
 // TODO: More Tests
 #[cfg(test)]
 mod tests {
@@ -476,6 +1454,20 @@ mod tests {
         Term::decode(io::Cursor::new(bytes)).expect("Decode failed")
     }
 
+    fn encode(term: Term) -> Vec<u8> {
+        let mut cursor = io::Cursor::new(vec![]);
+        term.encode(&mut cursor).expect("encode failed");
+        cursor.into_inner()
+    }
+
+    fn atom(name: &str) -> Term {
+        Term::Atom(eetf::Atom::from(name))
+    }
+
+    fn int(value: i32) -> Term {
+        Term::FixInteger(eetf::FixInteger::from(value))
+    }
+
     #[test]
     fn test_unsigned_ints_and_structs() {
         #[derive(PartialEq, Serialize)]
@@ -505,11 +1497,11 @@ mod tests {
                 ),
                 (
                     Term::Atom(eetf::Atom::from("unsigned32")),
-                    Term::BigInteger(eetf::BigInteger::from(65530))
+                    Term::FixInteger(eetf::FixInteger::from(65530))
                 ),
                 (
                     Term::Atom(eetf::Atom::from("unsigned64")),
-                    Term::BigInteger(eetf::BigInteger::from(65530))
+                    Term::FixInteger(eetf::FixInteger::from(65530))
                 )
             ]))
         )
@@ -527,11 +1519,37 @@ mod tests {
                 Term::FixInteger(eetf::FixInteger::from(-127)),
                 Term::FixInteger(eetf::FixInteger::from(30000)),
                 Term::FixInteger(eetf::FixInteger::from(65530)),
-                Term::BigInteger(eetf::BigInteger::from(65530)),
+                Term::FixInteger(eetf::FixInteger::from(65530)),
             ]))
         )
     }
 
+    #[test]
+    fn test_large_ints_are_big_integers() {
+        let big = |value: BigInt| Term::BigInteger(eetf::BigInteger { value });
+
+        assert_eq!(
+            serialize_and_decode(42i64),
+            Term::FixInteger(eetf::FixInteger::from(42))
+        );
+        assert_eq!(
+            serialize_and_decode(i64::from(i32::min_value())),
+            Term::FixInteger(eetf::FixInteger::from(i32::min_value()))
+        );
+        assert_eq!(
+            serialize_and_decode(i64::from(i32::min_value()) - 1),
+            big(BigInt::from(i64::from(i32::min_value()) - 1))
+        );
+        assert_eq!(
+            serialize_and_decode(i32::max_value() as u32 + 1),
+            big(BigInt::from(i32::max_value() as u32 + 1))
+        );
+        assert_eq!(
+            serialize_and_decode(u64::max_value()),
+            big(BigInt::from(u64::max_value()))
+        );
+    }
+
     #[test]
     fn test_binaries_tuples_and_lists() {
         let result = serialize_and_decode(("ABCD", vec![0, 1, 2]));
@@ -548,6 +1566,52 @@ mod tests {
         )
     }
 
+    #[test]
+    fn test_serde_bytes() {
+        use crate::from_bytes;
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Packet {
+            #[serde(with = "serde_bytes")]
+            payload: Vec<u8>,
+            checksum: Vec<u8>,
+        }
+
+        let byte = |value| Term::FixInteger(eetf::FixInteger::from(value));
+        let packet = Packet {
+            payload: vec![1, 2, 3],
+            checksum: vec![6],
+        };
+
+        let bytes = to_bytes(&packet).expect("serialize failed");
+        assert_eq!(
+            Term::decode(io::Cursor::new(&bytes)).expect("Decode failed"),
+            Term::Map(eetf::Map::from(vec![
+                (
+                    Term::Atom(eetf::Atom::from("payload")),
+                    Term::Binary(eetf::Binary::from(&[1, 2, 3][..])),
+                ),
+                (
+                    Term::Atom(eetf::Atom::from("checksum")),
+                    Term::List(eetf::List::from(vec![byte(6)])),
+                ),
+            ]))
+        );
+        let result: Packet = from_bytes(&bytes).expect("deserialize failed");
+        assert_eq!(result, packet);
+
+        // Either field can be deserialized from either representation.
+        let bytes = encode(Term::Map(eetf::Map::from(vec![
+            (
+                atom("payload"),
+                Term::List(eetf::List::from(vec![byte(1), byte(2), byte(3)])),
+            ),
+            (atom("checksum"), Term::Binary(eetf::Binary::from(&[6][..]))),
+        ])));
+        let result: Packet = from_bytes(&bytes).expect("deserialize failed");
+        assert_eq!(result, packet);
+    }
+
     #[test]
     fn test_option() {
         let none: Option<u8> = None;
@@ -559,6 +1623,192 @@ mod tests {
         assert_eq!(some_result, Term::FixInteger(eetf::FixInteger::from(0)));
     }
 
+    #[test]
+    fn test_config_applies_to_nested_values() {
+        use std::collections::BTreeMap;
+
+        #[derive(Serialize)]
+        struct Inner {
+            note: Option<u8>,
+        }
+
+        #[derive(Serialize)]
+        enum Event {
+            Update {
+                inner: Vec<Inner>,
+                extra: BTreeMap<u8, (Option<u8>,)>,
+            },
+        }
+
+        let mut extra = BTreeMap::new();
+        extra.insert(1, (None,));
+        let event = Event::Update {
+            inner: vec![Inner { note: None }],
+            extra,
+        };
+        let config = SerializerConfig {
+            key_format: KeyFormat::Binary,
+            none_atom: "undefined".to_string(),
+            ..SerializerConfig::default()
+        };
+
+        let bytes = to_bytes_with_config(&event, &config).expect("serialize failed");
+        let binary = |name: &str| Term::Binary(eetf::Binary::from(name.as_bytes()));
+        let undefined = || Term::Atom(eetf::Atom::from("undefined"));
+        assert_eq!(
+            Term::decode(io::Cursor::new(&bytes)).expect("Decode failed"),
+            Term::Tuple(eetf::Tuple::from(vec![
+                Term::Atom(eetf::Atom::from("update")),
+                Term::Map(eetf::Map::from(vec![
+                    (
+                        binary("inner"),
+                        Term::List(eetf::List::from(vec![Term::Map(eetf::Map::from(vec![(
+                            binary("note"),
+                            undefined()
+                        )]))]))
+                    ),
+                    (
+                        binary("extra"),
+                        Term::Map(eetf::Map::from(vec![(
+                            Term::FixInteger(eetf::FixInteger::from(1)),
+                            Term::Tuple(eetf::Tuple::from(vec![undefined()]))
+                        )]))
+                    ),
+                ])),
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_map_keys_and_values_serialized_separately() {
+        use serde::ser::SerializeMap;
+
+        struct Pairs(Vec<(&'static str, u8)>);
+
+        impl Serialize for Pairs {
+            fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+            where
+                S: ser::Serializer,
+            {
+                let mut map = serializer.serialize_map(None)?;
+                for (key, value) in &self.0 {
+                    map.serialize_key(key)?;
+                    map.serialize_value(value)?;
+                }
+                map.end()
+            }
+        }
+
+        let result = serialize_and_decode(Pairs(vec![("a", 1)]));
+        assert_eq!(
+            result,
+            Term::Map(eetf::Map::from(vec![(
+                Term::Binary(eetf::Binary::from("a".as_bytes())),
+                Term::FixInteger(eetf::FixInteger::from(1)),
+            )]))
+        );
+    }
+
+    #[test]
+    fn test_builder() {
+        #[derive(Serialize)]
+        struct Reply {
+            status: Option<u8>,
+        }
+
+        let reply = Reply { status: None };
+        let config = SerializerConfig {
+            key_format: KeyFormat::Binary,
+            none_atom: "undefined".to_string(),
+            compress: true,
+            ..SerializerConfig::default()
+        };
+        let expected = to_bytes_with_config(&reply, &config).expect("serialize failed");
+
+        let builder = Serializer::builder()
+            .key_format(KeyFormat::Binary)
+            .none_atom("undefined")
+            .compress(true);
+        assert_eq!(builder.to_bytes(&reply), Ok(expected.clone()));
+
+        let mut bytes = Vec::new();
+        builder
+            .build()
+            .to_writer(&reply, &mut bytes)
+            .expect("serialize failed");
+        assert_eq!(bytes, expected);
+    }
+
+    #[test]
+    fn test_unit_formats() {
+        use crate::from_bytes;
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Ack;
+
+        let serialize = |unit_format| {
+            let config = SerializerConfig {
+                unit_format,
+                ..SerializerConfig::default()
+            };
+            to_bytes_with_config(&(Some(()), Ack), &config).expect("serialize failed")
+        };
+
+        let bytes = serialize(UnitFormat::EmptyTuple);
+        let empty_tuple = || Term::Tuple(eetf::Tuple::from(vec![]));
+        assert_eq!(
+            Term::decode(io::Cursor::new(&bytes)).expect("Decode failed"),
+            Term::Tuple(eetf::Tuple::from(vec![empty_tuple(), empty_tuple()]))
+        );
+        // Unlike nil, an empty tuple isn't confused with None.
+        let result: (Option<()>, Ack) = from_bytes(&bytes).expect("deserialize failed");
+        assert_eq!(result, (Some(()), Ack));
+
+        let bytes = serialize(UnitFormat::EmptyList);
+        let empty_list = || Term::List(eetf::List::from(vec![]));
+        assert_eq!(
+            Term::decode(io::Cursor::new(&bytes)).expect("Decode failed"),
+            Term::Tuple(eetf::Tuple::from(vec![empty_list(), empty_list()]))
+        );
+        let result: (Option<()>, Ack) = from_bytes(&bytes).expect("deserialize failed");
+        assert_eq!(result, (Some(()), Ack));
+
+        let bytes = serialize(UnitFormat::Nil);
+        let result: (Option<()>, Ack) = from_bytes(&bytes).expect("deserialize failed");
+        assert_eq!(result, (None, Ack));
+    }
+
+    #[test]
+    fn test_none_atom() {
+        use crate::{from_bytes_with_config, DeserializerConfig};
+
+        let config = SerializerConfig {
+            none_atom: "null".to_string(),
+            ..SerializerConfig::default()
+        };
+
+        let bytes = to_bytes_with_config(&None::<u8>, &config).expect("serialize failed");
+        assert_eq!(
+            Term::decode(io::Cursor::new(&bytes)).expect("Decode failed"),
+            Term::Atom(eetf::Atom::from("null"))
+        );
+        let bytes = to_bytes_with_config(&(), &config).expect("serialize failed");
+        assert_eq!(
+            Term::decode(io::Cursor::new(&bytes)).expect("Decode failed"),
+            Term::Atom(eetf::Atom::from("null"))
+        );
+
+        let config = DeserializerConfig {
+            none_atom: Some("null".to_string()),
+            ..DeserializerConfig::default()
+        };
+        let result: Option<u8> =
+            from_bytes_with_config(&bytes, &config).expect("deserialize failed");
+        assert_eq!(result, None);
+        let result: () = from_bytes_with_config(&bytes, &config).expect("deserialize failed");
+        assert_eq!(result, ());
+    }
+
     #[test]
     fn test_unit_variant() {
         #[derive(Serialize)]
@@ -592,6 +1842,112 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_result() {
+        use crate::from_bytes;
+
+        let ok: std::result::Result<u8, String> = Ok(1);
+        let bytes = to_bytes(&ok).expect("serialize failed");
+        assert_eq!(
+            Term::decode(io::Cursor::new(&bytes)).expect("Decode failed"),
+            Term::Tuple(eetf::Tuple::from(vec![
+                Term::Atom(eetf::Atom::from("ok")),
+                Term::FixInteger(eetf::FixInteger::from(1)),
+            ]))
+        );
+        let result: std::result::Result<u8, String> =
+            from_bytes(&bytes).expect("deserialize failed");
+        assert_eq!(result, ok);
+
+        let err: std::result::Result<u8, String> = Err("timeout".to_string());
+        let bytes = to_bytes(&err).expect("serialize failed");
+        assert_eq!(
+            Term::decode(io::Cursor::new(&bytes)).expect("Decode failed"),
+            Term::Tuple(eetf::Tuple::from(vec![
+                Term::Atom(eetf::Atom::from("error")),
+                Term::Binary(eetf::Binary::from("timeout".as_bytes())),
+            ]))
+        );
+        let result: std::result::Result<u8, String> =
+            from_bytes(&bytes).expect("deserialize failed");
+        assert_eq!(result, err);
+
+        // We used to tag errors with `err`, so that's still accepted.
+        let bytes = encode(Term::Tuple(eetf::Tuple::from(vec![
+            atom("err"),
+            Term::Binary(eetf::Binary::from("timeout".as_bytes())),
+        ])));
+        let result: std::result::Result<u8, String> =
+            from_bytes(&bytes).expect("deserialize failed");
+        assert_eq!(result, err);
+    }
+
+    #[test]
+    fn test_result_with_binary_variant_tags() {
+        use crate::from_bytes;
+
+        let config = SerializerConfig {
+            variant_tag: VariantTag::Binary,
+            ..SerializerConfig::default()
+        };
+
+        let ok: std::result::Result<u8, String> = Ok(1);
+        let bytes = to_bytes_with_config(&ok, &config).expect("serialize failed");
+        assert_eq!(
+            Term::decode(io::Cursor::new(&bytes)).expect("Decode failed"),
+            Term::Tuple(eetf::Tuple::from(vec![
+                Term::Binary(eetf::Binary::from("ok".as_bytes())),
+                Term::FixInteger(eetf::FixInteger::from(1)),
+            ]))
+        );
+        let result: std::result::Result<u8, String> =
+            from_bytes(&bytes).expect("deserialize failed");
+        assert_eq!(result, ok);
+    }
+
+    #[test]
+    fn test_enums_with_ok_and_err_variants_arent_results() {
+        use crate::{from_bytes, from_bytes_with_config, DeserializerConfig};
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        enum Outcome {
+            Ok(u8),
+            Err(u8),
+        }
+
+        // Only Result's errors are tagged with `error`.
+        let bytes = to_bytes(&Outcome::Err(1)).expect("serialize failed");
+        assert_eq!(
+            Term::decode(io::Cursor::new(&bytes)).expect("Decode failed"),
+            Term::Tuple(eetf::Tuple::from(vec![
+                Term::Atom(eetf::Atom::from("err")),
+                Term::FixInteger(eetf::FixInteger::from(1)),
+            ]))
+        );
+        let result: Outcome = from_bytes(&bytes).expect("deserialize failed");
+        assert_eq!(result, Outcome::Err(1));
+
+        let bytes = encode(Term::Tuple(eetf::Tuple::from(vec![atom("error"), int(1)])));
+        assert!(from_bytes::<Outcome>(&bytes).is_err());
+
+        // Nor are they found in lower case when variant names are used as is.
+        let config = SerializerConfig {
+            variant_case: VariantCase::AsIs,
+            ..SerializerConfig::default()
+        };
+        let de_config = DeserializerConfig {
+            variant_case: VariantCase::AsIs,
+            ..DeserializerConfig::default()
+        };
+        let bytes = to_bytes_with_config(&Outcome::Ok(1), &config).expect("serialize failed");
+        let result: Outcome =
+            from_bytes_with_config(&bytes, &de_config).expect("deserialize failed");
+        assert_eq!(result, Outcome::Ok(1));
+
+        let bytes = encode(Term::Tuple(eetf::Tuple::from(vec![atom("ok"), int(1)])));
+        assert!(from_bytes_with_config::<Outcome>(&bytes, &de_config).is_err());
+    }
+
     #[test]
     fn test_tuple_variant() {
         // Not 100% sure if this is a tuple variant or a newtype variant.
@@ -614,4 +1970,947 @@ mod tests {
             ]))
         );
     }
+
+    #[test]
+    fn test_binary_variant_tags() {
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        enum Message {
+            TextMessage(String),
+            Point(u8, u8),
+        };
+
+        let config = SerializerConfig {
+            variant_tag: VariantTag::Binary,
+            ..SerializerConfig::default()
+        };
+
+        let bytes = to_bytes_with_config(&Message::TextMessage("hi".to_string()), &config)
+            .expect("serialize failed");
+        assert_eq!(
+            Term::decode(io::Cursor::new(&bytes)).expect("Decode failed"),
+            Term::Tuple(eetf::Tuple::from(vec![
+                Term::Binary(eetf::Binary::from("text_message".as_bytes())),
+                Term::Binary(eetf::Binary::from("hi".as_bytes())),
+            ]))
+        );
+        let result: Message = crate::from_bytes(&bytes).expect("deserialize failed");
+        assert_eq!(result, Message::TextMessage("hi".to_string()));
+
+        let bytes = to_bytes_with_config(&Message::Point(1, 2), &config).expect("serialize failed");
+        let result: Message = crate::from_bytes(&bytes).expect("deserialize failed");
+        assert_eq!(result, Message::Point(1, 2));
+    }
+
+    #[test]
+    fn test_binary_keys() {
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        enum Shape {
+            Rect { width: u8, height: u8 },
+        };
+
+        let config = SerializerConfig {
+            key_format: KeyFormat::Binary,
+            ..SerializerConfig::default()
+        };
+
+        let shape = Shape::Rect {
+            width: 2,
+            height: 3,
+        };
+        let bytes = to_bytes_with_config(&shape, &config).expect("serialize failed");
+        assert_eq!(
+            Term::decode(io::Cursor::new(&bytes)).expect("Decode failed"),
+            Term::Tuple(eetf::Tuple::from(vec![
+                Term::Atom(eetf::Atom::from("rect")),
+                Term::Map(eetf::Map::from(vec![
+                    (
+                        Term::Binary(eetf::Binary::from("width".as_bytes())),
+                        Term::FixInteger(eetf::FixInteger::from(2))
+                    ),
+                    (
+                        Term::Binary(eetf::Binary::from("height".as_bytes())),
+                        Term::FixInteger(eetf::FixInteger::from(3))
+                    ),
+                ])),
+            ]))
+        );
+        let result: Shape = crate::from_bytes(&bytes).expect("deserialize failed");
+        assert_eq!(result, shape);
+    }
+
+    #[test]
+    fn test_binary_struct_keys() {
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct User {
+            name: String,
+        }
+
+        let user = User {
+            name: "jo".to_string(),
+        };
+        let name = |key: Term| {
+            Term::Map(eetf::Map::from(vec![(
+                key,
+                Term::Binary(eetf::Binary::from("jo".as_bytes())),
+            )]))
+        };
+
+        // Atom keys remain the default.
+        assert_eq!(
+            serialize_and_decode(&user),
+            name(Term::Atom(eetf::Atom::from("name")))
+        );
+
+        let config = SerializerConfig {
+            key_format: KeyFormat::Binary,
+            ..SerializerConfig::default()
+        };
+        let mut cursor = io::Cursor::new(vec![]);
+        to_writer_with_config(&user, &mut cursor, &config).expect("serialize failed");
+        let bytes = cursor.into_inner();
+        assert_eq!(
+            Term::decode(io::Cursor::new(&bytes)).expect("Decode failed"),
+            name(Term::Binary(eetf::Binary::from("name".as_bytes())))
+        );
+        let result: User = crate::from_bytes(&bytes).expect("deserialize failed");
+        assert_eq!(result, user);
+    }
+
+    #[test]
+    fn test_field_names_are_verbatim() {
+        #[allow(non_snake_case)]
+        #[derive(Serialize)]
+        struct User {
+            userId: u8,
+        }
+
+        assert_eq!(
+            serialize_and_decode(User { userId: 1 }),
+            Term::Map(eetf::Map::from(vec![(
+                Term::Atom(eetf::Atom::from("userId")),
+                Term::FixInteger(eetf::FixInteger::from(1))
+            )]))
+        );
+    }
+
+    #[test]
+    fn test_empty_map_formats() {
+        use crate::{from_bytes_with_config, DeserializerConfig};
+        use std::collections::HashMap;
+
+        let map: HashMap<String, u8> = HashMap::new();
+        let de_config = DeserializerConfig {
+            accept_nil_maps: true,
+            ..DeserializerConfig::default()
+        };
+
+        for (format, term) in vec![
+            (EmptyMapFormat::Map, Term::Map(eetf::Map::from(vec![]))),
+            (EmptyMapFormat::Nil, Term::Atom(eetf::Atom::from("nil"))),
+            (EmptyMapFormat::List, Term::List(eetf::List::from(vec![]))),
+        ] {
+            let config = SerializerConfig {
+                empty_map_format: format,
+                ..SerializerConfig::default()
+            };
+            let bytes = to_bytes_with_config(&map, &config).expect("serialize failed");
+            assert_eq!(
+                Term::decode(io::Cursor::new(&bytes)).expect("Decode failed"),
+                term
+            );
+            let result: HashMap<String, u8> =
+                from_bytes_with_config(&bytes, &de_config).expect("deserialize failed");
+            assert_eq!(result, map);
+        }
+
+        // Only empty maps are affected.
+        let mut map = HashMap::new();
+        map.insert("a".to_string(), 1);
+        let config = SerializerConfig {
+            empty_map_format: EmptyMapFormat::Nil,
+            ..SerializerConfig::default()
+        };
+        let bytes = to_bytes_with_config(&map, &config).expect("serialize failed");
+        assert_eq!(
+            Term::decode(io::Cursor::new(&bytes)).expect("Decode failed"),
+            Term::Map(eetf::Map::from(vec![(
+                Term::Binary(eetf::Binary::from("a".as_bytes())),
+                Term::FixInteger(eetf::FixInteger::from(1))
+            )]))
+        );
+
+        // Empty maps are encoded with the same atom as None.
+        let config = SerializerConfig {
+            empty_map_format: EmptyMapFormat::Nil,
+            none_atom: "undefined".to_string(),
+            ..SerializerConfig::default()
+        };
+        let empty: HashMap<String, u8> = HashMap::new();
+        let bytes = to_bytes_with_config(&empty, &config).expect("serialize failed");
+        assert_eq!(
+            Term::decode(io::Cursor::new(&bytes)).expect("Decode failed"),
+            atom("undefined")
+        );
+        let result: HashMap<String, u8> =
+            from_bytes_with_config(&bytes, &de_config).expect("deserialize failed");
+        assert_eq!(result, empty);
+    }
+
+    #[test]
+    fn test_flat_tuple_variants() {
+        use crate::{from_bytes_with_config, DeserializerConfig};
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        enum Command {
+            Stop,
+            Wait(u8),
+            Move(u8, u8),
+            Resize { width: u8, height: u8 },
+        }
+
+        let config = SerializerConfig {
+            variant_format: VariantFormat::FlatTuple,
+            ..SerializerConfig::default()
+        };
+        let de_config = DeserializerConfig {
+            variant_format: VariantFormat::FlatTuple,
+            ..DeserializerConfig::default()
+        };
+
+        for (command, term) in vec![
+            (Command::Stop, atom("stop")),
+            (
+                Command::Wait(5),
+                Term::Tuple(eetf::Tuple::from(vec![atom("wait"), int(5)])),
+            ),
+            (
+                Command::Move(1, 2),
+                Term::Tuple(eetf::Tuple::from(vec![atom("move"), int(1), int(2)])),
+            ),
+            (
+                Command::Resize {
+                    width: 3,
+                    height: 4,
+                },
+                Term::Tuple(eetf::Tuple::from(vec![atom("resize"), int(3), int(4)])),
+            ),
+        ] {
+            let bytes = to_bytes_with_config(&command, &config).expect("serialize failed");
+            assert_eq!(
+                Term::decode(io::Cursor::new(&bytes)).expect("Decode failed"),
+                term
+            );
+            let result: Command =
+                from_bytes_with_config(&bytes, &de_config).expect("deserialize failed");
+            assert_eq!(result, command);
+        }
+    }
+
+    #[test]
+    fn test_exact_variant_names() {
+        use crate::{from_bytes_with_config, DeserializerConfig};
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        enum Message {
+            TextMessage,
+            Ping(u8),
+        }
+
+        let config = SerializerConfig {
+            variant_case: VariantCase::AsIs,
+            ..SerializerConfig::default()
+        };
+        let de_config = DeserializerConfig {
+            variant_case: VariantCase::AsIs,
+            ..DeserializerConfig::default()
+        };
+
+        let bytes = to_bytes_with_config(&Message::TextMessage, &config).expect("serialize failed");
+        assert_eq!(
+            Term::decode(io::Cursor::new(&bytes)).expect("Decode failed"),
+            Term::Atom(eetf::Atom::from("TextMessage"))
+        );
+        let result: Message =
+            from_bytes_with_config(&bytes, &de_config).expect("deserialize failed");
+        assert_eq!(result, Message::TextMessage);
+
+        let bytes = to_bytes_with_config(&Message::Ping(1), &config).expect("serialize failed");
+        assert_eq!(
+            Term::decode(io::Cursor::new(&bytes)).expect("Decode failed"),
+            Term::Tuple(eetf::Tuple::from(vec![
+                Term::Atom(eetf::Atom::from("Ping")),
+                Term::FixInteger(eetf::FixInteger::from(1)),
+            ]))
+        );
+        let result: Message =
+            from_bytes_with_config(&bytes, &de_config).expect("deserialize failed");
+        assert_eq!(result, Message::Ping(1));
+
+        // Tags aren't converted, so must match exactly.
+        let bytes = to_bytes(&Message::TextMessage).expect("serialize failed");
+        assert!(from_bytes_with_config::<Message>(&bytes, &de_config).is_err());
+    }
+
+    #[test]
+    fn test_exact_variant_names_with_rename_all() {
+        #[derive(Serialize)]
+        #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+        enum Level {
+            LogWarning,
+        }
+
+        // By default the renamed variant is snake_cased again.
+        let bytes = to_bytes(&Level::LogWarning).expect("serialize failed");
+        assert_eq!(
+            Term::decode(io::Cursor::new(&bytes)).expect("Decode failed"),
+            Term::Atom(eetf::Atom::from("log_warning"))
+        );
+
+        let config = SerializerConfig {
+            variant_case: VariantCase::AsIs,
+            ..SerializerConfig::default()
+        };
+        let bytes = to_bytes_with_config(&Level::LogWarning, &config).expect("serialize failed");
+        assert_eq!(
+            Term::decode(io::Cursor::new(&bytes)).expect("Decode failed"),
+            Term::Atom(eetf::Atom::from("LOG_WARNING"))
+        );
+    }
+
+    #[test]
+    fn test_sort_map_keys() {
+        use std::collections::HashMap;
+
+        let config = SerializerConfig {
+            sort_map_keys: true,
+            ..SerializerConfig::default()
+        };
+        let map: HashMap<String, u8> = ["c", "a", "ab", "b"]
+            .iter()
+            .enumerate()
+            .map(|(i, key)| (key.to_string(), i as u8))
+            .collect();
+
+        let bytes = to_bytes_with_config(&map, &config).expect("serialize failed");
+        match Term::decode(io::Cursor::new(&bytes)).expect("Decode failed") {
+            Term::Map(map) => {
+                let keys: Vec<Term> = map.entries.into_iter().map(|(key, _)| key).collect();
+                let binary = |key: &str| Term::Binary(eetf::Binary::from(key.as_bytes()));
+                assert_eq!(
+                    keys,
+                    vec![binary("a"), binary("ab"), binary("b"), binary("c")]
+                );
+            }
+            other => panic!("Expected a map, got {}", other),
+        }
+    }
+
+    #[test]
+    fn test_term_order() {
+        let expected = vec![
+            int(-1),
+            Term::Float(eetf::Float { value: 1.5 }),
+            Term::BigInteger(eetf::BigInteger::from(5_000_000_000i64)),
+            Term::Atom(eetf::Atom::from("a")),
+            Term::Atom(eetf::Atom::from("b")),
+            Term::Tuple(eetf::Tuple::from(vec![int(3)])),
+            Term::Tuple(eetf::Tuple::from(vec![int(1), int(2)])),
+            Term::List(eetf::List::from(vec![])),
+            Term::List(eetf::List::from(vec![int(1)])),
+            Term::Binary(eetf::Binary::from("a".as_bytes())),
+        ];
+
+        let mut terms: Vec<Term> = expected.iter().cloned().rev().collect();
+        terms.sort_by(term_cmp);
+
+        assert_eq!(terms, expected);
+    }
+
+    #[test]
+    fn test_map_key_formats() {
+        use std::collections::BTreeMap;
+
+        let mut map = BTreeMap::new();
+        map.insert("user_id", 1);
+        map.insert("Display Name", 2);
+
+        let keys = |config: &SerializerConfig| -> Vec<Term> {
+            let bytes = to_bytes_with_config(&map, config).expect("serialize failed");
+            match Term::decode(io::Cursor::new(&bytes)).expect("Decode failed") {
+                Term::Map(map) => map.entries.into_iter().map(|(key, _)| key).collect(),
+                other => panic!("Expected a map, got {:?}", other),
+            }
+        };
+        let binary = |name: &str| Term::Binary(eetf::Binary::from(name.as_bytes()));
+
+        assert_eq!(
+            keys(&SerializerConfig::default()),
+            vec![binary("Display Name"), binary("user_id")]
+        );
+        assert_eq!(
+            keys(&SerializerConfig {
+                map_key_format: MapKeyFormat::Atom,
+                ..SerializerConfig::default()
+            }),
+            vec![atom("Display Name"), atom("user_id")]
+        );
+        assert_eq!(
+            keys(&SerializerConfig {
+                map_key_format: MapKeyFormat::SafeAtom,
+                ..SerializerConfig::default()
+            }),
+            vec![binary("Display Name"), atom("user_id")]
+        );
+    }
+
+    #[test]
+    fn test_is_safe_atom() {
+        assert!(is_safe_atom("ok"));
+        assert!(is_safe_atom("user_id2"));
+        assert!(!is_safe_atom(""));
+        assert!(!is_safe_atom("_private"));
+        assert!(!is_safe_atom("2fast"));
+        assert!(!is_safe_atom("Upper"));
+        assert!(!is_safe_atom("has space"));
+        assert!(!is_safe_atom("caf\u{e9}"));
+    }
+
+    #[test]
+    fn test_count_distinct_atoms() {
+        use std::collections::HashMap;
+
+        #[derive(Serialize)]
+        enum Status {
+            Active,
+            Suspended,
+        }
+
+        #[derive(Serialize)]
+        struct Account {
+            status: Status,
+            tags: Vec<Status>,
+            owner: Option<String>,
+        }
+
+        let accounts = vec![
+            Account {
+                status: Status::Active,
+                tags: vec![Status::Suspended],
+                owner: None,
+            },
+            Account {
+                status: Status::Active,
+                tags: vec![],
+                owner: Some("me".to_string()),
+            },
+        ];
+
+        // status, tags, owner, active, suspended & nil.
+        assert_eq!(count_distinct_atoms(&accounts).expect("count failed"), 6);
+
+        let mut map = HashMap::new();
+        map.insert("first", 1);
+        map.insert("second", 2);
+        assert_eq!(count_distinct_atoms(&map).expect("count failed"), 0);
+        let config = SerializerConfig {
+            map_key_format: MapKeyFormat::Atom,
+            ..SerializerConfig::default()
+        };
+        assert_eq!(
+            count_distinct_atoms_with_config(&map, &config).expect("count failed"),
+            2
+        );
+    }
+
+    #[test]
+    fn test_to_term() {
+        #[derive(Serialize)]
+        struct Point(u8, u8);
+
+        assert_eq!(
+            to_term(&Point(1, 2)).expect("serialize failed"),
+            Term::Tuple(eetf::Tuple::from(vec![
+                Term::FixInteger(eetf::FixInteger::from(1)),
+                Term::FixInteger(eetf::FixInteger::from(2)),
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_eetf_eq() {
+        use std::collections::HashMap;
+
+        #[derive(Serialize)]
+        struct Point {
+            x: u8,
+            y: u8,
+        }
+        #[derive(Serialize, PartialEq, Eq, Hash)]
+        enum Key {
+            X,
+            Y,
+        }
+
+        let mut map = HashMap::new();
+        map.insert(Key::Y, 2);
+        map.insert(Key::X, 1);
+        assert_eq!(eetf_eq(&Point { x: 1, y: 2 }, &map), Ok(true));
+
+        map.insert(Key::Y, 3);
+        assert_eq!(eetf_eq(&Point { x: 1, y: 2 }, &map), Ok(false));
+
+        assert_eq!(eetf_eq(&(1, 2), &vec![1, 2]), Ok(false));
+        assert_eq!(eetf_eq("abc", &"abc".to_string()), Ok(true));
+    }
+
+    #[test]
+    fn test_tuple_struct_formats() {
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct MapPoint(i32, i32);
+
+        let bytes = to_bytes(&MapPoint(1, 2)).expect("serialize failed");
+        assert_eq!(
+            Term::decode(io::Cursor::new(&bytes)).expect("Decode failed"),
+            Term::Tuple(eetf::Tuple::from(vec![int(1), int(2)]))
+        );
+        let result: MapPoint = crate::from_bytes(&bytes).expect("deserialize failed");
+        assert_eq!(result, MapPoint(1, 2));
+
+        let config = SerializerConfig {
+            tuple_struct_format: TupleStructFormat::Record,
+            ..SerializerConfig::default()
+        };
+        let bytes = to_bytes_with_config(&MapPoint(1, 2), &config).expect("serialize failed");
+        assert_eq!(
+            Term::decode(io::Cursor::new(&bytes)).expect("Decode failed"),
+            Term::Tuple(eetf::Tuple::from(vec![
+                Term::Atom(eetf::Atom::from("map_point")),
+                int(1),
+                int(2)
+            ]))
+        );
+        let result: MapPoint = crate::from_bytes(&bytes).expect("deserialize failed");
+        assert_eq!(result, MapPoint(1, 2));
+    }
+
+    #[test]
+    fn test_record_structs() {
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct UserRecord {
+            name: String,
+            age: u8,
+        }
+
+        let user = UserRecord {
+            name: "bob".to_string(),
+            age: 30,
+        };
+        let config = SerializerConfig {
+            struct_format: StructFormat::Record,
+            ..SerializerConfig::default()
+        };
+
+        let bytes = to_bytes_with_config(&user, &config).expect("serialize failed");
+        assert_eq!(
+            Term::decode(io::Cursor::new(&bytes)).expect("Decode failed"),
+            Term::Tuple(eetf::Tuple::from(vec![
+                Term::Atom(eetf::Atom::from("user_record")),
+                Term::Binary(eetf::Binary::from("bob".as_bytes())),
+                Term::FixInteger(eetf::FixInteger::from(30)),
+            ]))
+        );
+        let result: UserRecord = crate::from_bytes(&bytes).expect("deserialize failed");
+        assert_eq!(result, user);
+    }
+
+    #[test]
+    fn test_record_structs_cant_skip_fields() {
+        #[derive(Serialize)]
+        struct UserRecord {
+            name: String,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            age: Option<u8>,
+        }
+
+        let user = UserRecord {
+            name: "bob".to_string(),
+            age: None,
+        };
+        let config = SerializerConfig {
+            struct_format: StructFormat::Record,
+            ..SerializerConfig::default()
+        };
+
+        assert!(to_bytes(&user).is_ok());
+        assert_eq!(
+            to_bytes_with_config(&user, &config),
+            Err(Error::Message(
+                "field `age` can't be skipped when fields are encoded by position".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_newtype_struct_formats() {
+        use crate::{from_bytes_with_config, DeserializerConfig};
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct UserId(i32);
+
+        let bytes = to_bytes(&UserId(1)).expect("serialize failed");
+        assert_eq!(
+            Term::decode(io::Cursor::new(&bytes)).expect("Decode failed"),
+            Term::FixInteger(eetf::FixInteger::from(1))
+        );
+        let result: UserId = crate::from_bytes(&bytes).expect("deserialize failed");
+        assert_eq!(result, UserId(1));
+
+        let config = SerializerConfig {
+            newtype_struct_format: NewtypeStructFormat::Tagged,
+            ..SerializerConfig::default()
+        };
+        let bytes = to_bytes_with_config(&UserId(1), &config).expect("serialize failed");
+        assert_eq!(
+            Term::decode(io::Cursor::new(&bytes)).expect("Decode failed"),
+            Term::Tuple(eetf::Tuple::from(vec![
+                Term::Atom(eetf::Atom::from("user_id")),
+                Term::FixInteger(eetf::FixInteger::from(1)),
+            ]))
+        );
+        let config = DeserializerConfig {
+            newtype_struct_format: NewtypeStructFormat::Tagged,
+            ..DeserializerConfig::default()
+        };
+        let result: UserId = from_bytes_with_config(&bytes, &config).expect("deserialize failed");
+        assert_eq!(result, UserId(1));
+
+        let bytes = to_bytes(&UserId(1)).expect("serialize failed");
+        assert!(from_bytes_with_config::<UserId>(&bytes, &config).is_err());
+    }
+
+    #[test]
+    fn test_latin1_atoms() {
+        use std::collections::BTreeMap;
+
+        #[derive(Serialize)]
+        enum Shape {
+            Circle(u8),
+            Rect { width: u8, height: u8 },
+        }
+
+        let config = SerializerConfig {
+            atom_encoding: AtomEncoding::Latin1,
+            map_key_format: MapKeyFormat::Atom,
+            ..SerializerConfig::default()
+        };
+
+        let mut map = BTreeMap::new();
+        map.insert("café", vec![Shape::Circle(1)]);
+        map.insert(
+            "tea",
+            vec![Shape::Rect {
+                width: 2,
+                height: 3,
+            }],
+        );
+
+        let bytes = to_bytes_with_config(&map, &config).expect("serialize failed");
+        // café as a SMALL_ATOM_EXT, in Latin-1.
+        assert!(bytes
+            .windows(6)
+            .any(|window| window == [SMALL_ATOM_EXT, 4, b'c', b'a', b'f', 0xE9]));
+        let utf8_config = SerializerConfig {
+            map_key_format: MapKeyFormat::Atom,
+            ..SerializerConfig::default()
+        };
+        let utf8_bytes = to_bytes_with_config(&map, &utf8_config).expect("serialize failed");
+        assert_eq!(
+            Term::decode(io::Cursor::new(&bytes)).expect("Decode failed"),
+            Term::decode(io::Cursor::new(&utf8_bytes)).expect("Decode failed")
+        );
+
+        let mut map = BTreeMap::new();
+        map.insert("日本", 1);
+        assert!(to_bytes_with_config(&map, &config).is_err());
+    }
+
+    #[test]
+    fn test_latin1_pids() {
+        let pid = crate::Pid {
+            node: "café@localhost".to_string(),
+            id: 42,
+            serial: 1,
+            creation: 2,
+        };
+        let config = SerializerConfig {
+            atom_encoding: AtomEncoding::Latin1,
+            ..SerializerConfig::default()
+        };
+
+        let bytes = to_bytes_with_config(&("café", &pid), &config).expect("serialize failed");
+        assert!(bytes
+            .windows(3)
+            .any(|window| window == [PID_EXT, SMALL_ATOM_EXT, 14]));
+        assert_eq!(
+            Term::decode(io::Cursor::new(&bytes)).expect("Decode failed"),
+            Term::Tuple(eetf::Tuple::from(vec![
+                Term::Binary(eetf::Binary::from("café".as_bytes())),
+                Term::Pid(eetf::Pid::from(pid)),
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_compressed_roundtrip() {
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Reading {
+            sensor: String,
+            value: u8,
+        }
+
+        let readings: Vec<Reading> = (0..500)
+            .map(|i| Reading {
+                sensor: "temperature".to_string(),
+                value: (i % 4) as u8,
+            })
+            .collect();
+
+        let bytes = to_bytes(&readings).expect("serialize failed");
+        let compressed = to_bytes_compressed(&readings).expect("serialize failed");
+
+        assert_eq!(compressed[..2], [131, COMPRESSED_TAG]);
+        assert!(compressed.len() < bytes.len() / 10);
+        assert_eq!(
+            Term::decode(io::Cursor::new(&compressed)).expect("Decode failed"),
+            Term::decode(io::Cursor::new(&bytes)).expect("Decode failed")
+        );
+        let result: Vec<Reading> = crate::from_bytes(&compressed).expect("deserialize failed");
+        assert_eq!(result, readings);
+    }
+
+    #[test]
+    fn test_no_version() {
+        let value = (1, "two", vec![3.0]);
+
+        let bytes = to_bytes(&value).expect("serialize failed");
+        let body = to_bytes_no_version(&value).expect("serialize failed");
+        assert_eq!(bytes[0], VERSION);
+        assert_eq!(body, bytes[1..].to_vec());
+
+        let mut cursor = io::Cursor::new(vec![]);
+        to_writer_no_version(&value, &mut cursor).expect("serialize failed");
+        assert_eq!(cursor.into_inner(), body);
+
+        let result: (i32, String, Vec<f64>) =
+            crate::from_bytes_no_version(&body).expect("deserialize failed");
+        assert_eq!(result, (1, "two".to_string(), vec![3.0]));
+    }
+
+    #[test]
+    fn test_encode_errors() {
+        use std::collections::HashMap;
+
+        // Atoms can't be longer than 65535 bytes.
+        let mut map = HashMap::new();
+        map.insert("a".repeat(70000), 1);
+        let config = SerializerConfig {
+            map_key_format: MapKeyFormat::Atom,
+            ..SerializerConfig::default()
+        };
+
+        match to_bytes_with_config(&map, &config) {
+            Err(Error::EncodeError(message)) => assert_ne!(message, "TODO"),
+            other => panic!("Expected an EncodeError, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_charlist_strings_and_chars() {
+        #[derive(Serialize)]
+        struct Person {
+            name: String,
+            initial: char,
+        }
+
+        let config = SerializerConfig {
+            string_format: StringFormat::CharList,
+            ..SerializerConfig::default()
+        };
+        let bytes = to_bytes_with_config(
+            &Person {
+                name: "Jo".to_string(),
+                initial: 'J',
+            },
+            &config,
+        )
+        .expect("serialize failed");
+
+        assert_eq!(
+            Term::decode(io::Cursor::new(&bytes)).expect("Decode failed"),
+            Term::Map(eetf::Map::from(vec![
+                (
+                    Term::Atom(eetf::Atom::from("name")),
+                    Term::List(eetf::List::from(vec![
+                        Term::FixInteger(eetf::FixInteger::from(74)),
+                        Term::FixInteger(eetf::FixInteger::from(111)),
+                    ]))
+                ),
+                (
+                    Term::Atom(eetf::Atom::from("initial")),
+                    Term::FixInteger(eetf::FixInteger::from(74))
+                ),
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_integer_chars() {
+        let config = SerializerConfig {
+            char_format: CharFormat::Integer,
+            ..SerializerConfig::default()
+        };
+
+        let bytes = to_bytes_with_config(&('A', "A"), &config).expect("serialize failed");
+        assert_eq!(
+            Term::decode(io::Cursor::new(&bytes)).expect("Decode failed"),
+            Term::Tuple(eetf::Tuple::from(vec![
+                Term::FixInteger(eetf::FixInteger::from(65)),
+                Term::Binary(eetf::Binary::from("A".as_bytes())),
+            ]))
+        );
+        let result: (char, String) = crate::from_bytes(&bytes).expect("deserialize failed");
+        assert_eq!(result, ('A', "A".to_string()));
+    }
+
+    #[test]
+    fn test_charlists_use_scalar_values() {
+        let config = SerializerConfig {
+            string_format: StringFormat::CharList,
+            ..SerializerConfig::default()
+        };
+        let bytes = to_bytes_with_config("né😀", &config).expect("serialize failed");
+
+        assert_eq!(
+            Term::decode(io::Cursor::new(&bytes)).expect("Decode failed"),
+            Term::List(eetf::List::from(vec![
+                Term::FixInteger(eetf::FixInteger::from(110)),
+                Term::FixInteger(eetf::FixInteger::from(233)),
+                Term::FixInteger(eetf::FixInteger::from(0x1F600)),
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_string_floats() {
+        let config = SerializerConfig {
+            float_format: FloatFormat::String,
+            ..SerializerConfig::default()
+        };
+
+        for (value, string) in &[
+            (0.1, "0.1"),
+            (1.0, "1.0"),
+            (-3.0, "-3.0"),
+            (1e308, "1.0e308"),
+            (-2.5e-300, "-2.5e-300"),
+            (0.30000000000000004, "0.30000000000000004"),
+        ] {
+            let bytes = to_bytes_with_config(value, &config).expect("serialize failed");
+            assert_eq!(
+                Term::decode(io::Cursor::new(&bytes)).expect("Decode failed"),
+                Term::Binary(eetf::Binary::from(string.as_bytes()))
+            );
+            let result: f64 = crate::from_bytes(&bytes).expect("deserialize failed");
+            assert_eq!(result, *value);
+        }
+    }
+
+    #[test]
+    fn test_nan_handling() {
+        assert!(to_bytes(&std::f64::NAN).is_err());
+
+        let config = SerializerConfig {
+            nan_handling: NanHandling::Atom,
+            ..SerializerConfig::default()
+        };
+        for (value, name) in &[
+            (std::f64::INFINITY, "infinity"),
+            (std::f64::NEG_INFINITY, "neg_infinity"),
+        ] {
+            let bytes = to_bytes_with_config(value, &config).expect("serialize failed");
+            assert_eq!(
+                Term::decode(io::Cursor::new(&bytes)).expect("Decode failed"),
+                Term::Atom(eetf::Atom::from(*name))
+            );
+            let result: f64 = crate::from_bytes(&bytes).expect("deserialize failed");
+            assert_eq!(result, *value);
+        }
+
+        let bytes = to_bytes_with_config(&std::f64::NAN, &config).expect("serialize failed");
+        assert_eq!(
+            Term::decode(io::Cursor::new(&bytes)).expect("Decode failed"),
+            Term::Atom(eetf::Atom::from("nan"))
+        );
+        let result: f32 = crate::from_bytes(&bytes).expect("deserialize failed");
+        assert!(result.is_nan());
+    }
+
+    #[test]
+    fn test_integer_bools() {
+        let config = SerializerConfig {
+            bool_format: BoolFormat::Integer,
+            ..SerializerConfig::default()
+        };
+
+        let bytes = to_bytes_with_config(&(true, false), &config).expect("serialize failed");
+        assert_eq!(
+            Term::decode(io::Cursor::new(&bytes)).expect("Decode failed"),
+            Term::Tuple(eetf::Tuple::from(vec![
+                Term::FixInteger(eetf::FixInteger::from(1)),
+                Term::FixInteger(eetf::FixInteger::from(0)),
+            ]))
+        );
+
+        let result: (bool, bool) = crate::from_bytes(&bytes).expect("deserialize failed");
+        assert_eq!(result, (true, false));
+    }
+
+    #[test]
+    fn test_non_string_map_keys() {
+        use std::collections::HashMap;
+
+        let mut by_int = HashMap::new();
+        by_int.insert(1, "one".to_string());
+        assert_eq!(
+            serialize_and_decode(&by_int),
+            Term::Map(eetf::Map::from(vec![(
+                Term::FixInteger(eetf::FixInteger::from(1)),
+                Term::Binary(eetf::Binary::from("one".as_bytes())),
+            )]))
+        );
+
+        let mut by_tuple = HashMap::new();
+        by_tuple.insert((1, 2), true);
+        assert_eq!(
+            serialize_and_decode(&by_tuple),
+            Term::Map(eetf::Map::from(vec![(
+                Term::Tuple(eetf::Tuple::from(vec![
+                    Term::FixInteger(eetf::FixInteger::from(1)),
+                    Term::FixInteger(eetf::FixInteger::from(2)),
+                ])),
+                Term::Atom(eetf::Atom::from("true")),
+            )]))
+        );
+
+        by_int.insert(2, "two".to_string());
+        let bytes = to_bytes(&by_int).expect("serialize failed");
+        let result: HashMap<i32, String> = crate::from_bytes(&bytes).expect("deserialize failed");
+        assert_eq!(result, by_int);
+
+        by_tuple.insert((3, 4), false);
+        let bytes = to_bytes(&by_tuple).expect("serialize failed");
+        let result: HashMap<(i32, i32), bool> =
+            crate::from_bytes(&bytes).expect("deserialize failed");
+        assert_eq!(result, by_tuple);
+    }
 }
@@ -0,0 +1,253 @@
+//! Support for the various ways that times are represented in Erlang.
+
+use std::fmt;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::de::{self, Deserialize, Deserializer, SeqAccess, Visitor};
+
+/// A point in time that can be deserialized from any of the common Erlang
+/// time representations.
+///
+/// The shape of the term decides how it is interpreted:
+///
+/// 1. An integer is a number of milliseconds since the unix epoch, as returned
+///    by `erlang:system_time(millisecond)`.
+/// 2. A 3-tuple of integers is an `erlang:timestamp()`, i.e.
+///    `{MegaSecs, Secs, MicroSecs}`.
+/// 3. A 2-tuple of integer 3-tuples is a `calendar:datetime()`, i.e.
+///    `{{Year, Month, Day}, {Hour, Minute, Second}}`.  This is assumed to be
+///    in UTC.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FlexibleTime(pub SystemTime);
+
+impl From<FlexibleTime> for SystemTime {
+    fn from(time: FlexibleTime) -> SystemTime {
+        time.0
+    }
+}
+
+impl<'de> Deserialize<'de> for FlexibleTime {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(FlexibleTimeVisitor)
+    }
+}
+
+struct FlexibleTimeVisitor;
+
+impl<'de> Visitor<'de> for FlexibleTimeVisitor {
+    type Value = FlexibleTime;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("unix milliseconds, an erlang timestamp or a calendar datetime")
+    }
+
+    fn visit_i64<E>(self, millis: i64) -> Result<FlexibleTime, E>
+    where
+        E: de::Error,
+    {
+        // wrapping_neg gives the correct magnitude even for i64::MIN, once it's
+        // cast to a u64.
+        let offset = Duration::from_millis(millis.wrapping_neg() as u64);
+        if millis < 0 {
+            from_epoch(UNIX_EPOCH.checked_sub(offset))
+        } else {
+            self.visit_u64(millis as u64)
+        }
+    }
+
+    fn visit_u64<E>(self, millis: u64) -> Result<FlexibleTime, E>
+    where
+        E: de::Error,
+    {
+        from_epoch(UNIX_EPOCH.checked_add(Duration::from_millis(millis)))
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<FlexibleTime, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut parts = Vec::with_capacity(3);
+        while let Some(part) = seq.next_element::<Part>()? {
+            parts.push(part);
+        }
+
+        match parts.as_slice() {
+            [Part::Integer(mega_secs), Part::Integer(secs), Part::Integer(micro_secs)] => {
+                if *mega_secs < 0 || *secs < 0 || *micro_secs < 0 {
+                    return Err(de::Error::custom("timestamp components must not be negative"));
+                }
+                let offset = Duration::from_secs(*secs as u64)
+                    + Duration::from_micros(*micro_secs as u64);
+                from_epoch(
+                    (*mega_secs as u64)
+                        .checked_mul(1_000_000)
+                        .map(Duration::from_secs)
+                        .and_then(|mega_secs| UNIX_EPOCH.checked_add(mega_secs))
+                        .and_then(|time| time.checked_add(offset)),
+                )
+            }
+            [Part::Triple(year, month, day), Part::Triple(hour, minute, second)] => {
+                // SystemTime can't represent anything much more than a
+                // billion years from now anyway.
+                let valid = in_range(*year, -1_000_000_000, 1_000_000_000)
+                    && in_range(*month, 1, 12)
+                    && in_range(*day, 1, 31)
+                    && in_range(*hour, 0, 23)
+                    && in_range(*minute, 0, 59)
+                    && in_range(*second, 0, 59);
+                if !valid {
+                    return Err(de::Error::custom("invalid calendar datetime"));
+                }
+                let secs = days_from_civil(*year, *month, *day) * 86400
+                    + hour * 3600
+                    + minute * 60
+                    + second;
+                let offset = Duration::from_secs(secs.wrapping_neg() as u64);
+                if secs < 0 {
+                    from_epoch(UNIX_EPOCH.checked_sub(offset))
+                } else {
+                    from_epoch(UNIX_EPOCH.checked_add(Duration::from_secs(secs as u64)))
+                }
+            }
+            _ => Err(de::Error::invalid_length(parts.len(), &self)),
+        }
+    }
+}
+
+fn from_epoch<E>(time: Option<SystemTime>) -> Result<FlexibleTime, E>
+where
+    E: de::Error,
+{
+    time.map(FlexibleTime)
+        .ok_or_else(|| de::Error::custom("time out of range"))
+}
+
+fn in_range(value: i64, min: i64, max: i64) -> bool {
+    value >= min && value <= max
+}
+
+// The elements of the tuple based time representations.
+enum Part {
+    Integer(i64),
+    Triple(i64, i64, i64),
+}
+
+impl<'de> Deserialize<'de> for Part {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(PartVisitor)
+    }
+}
+
+struct PartVisitor;
+
+impl<'de> Visitor<'de> for PartVisitor {
+    type Value = Part;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an integer or a tuple of three integers")
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Part, E>
+    where
+        E: de::Error,
+    {
+        Ok(Part::Integer(v))
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Part, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut elements = [0; 3];
+        for (index, element) in elements.iter_mut().enumerate() {
+            match seq.next_element()? {
+                Some(value) => *element = value,
+                None => return Err(de::Error::invalid_length(index, &self)),
+            }
+        }
+        if seq.next_element::<i64>()?.is_some() {
+            return Err(de::Error::invalid_length(4, &self));
+        }
+        Ok(Part::Triple(elements[0], elements[1], elements[2]))
+    }
+}
+
+// Converts a date in the proleptic gregorian calendar into a number of days
+// since the unix epoch.  See http://howardhinnant.github.io/date_algorithms.html
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = (if year >= 0 { year } else { year - 399 }) / 400;
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io;
+
+    use eetf::{self, Term};
+
+    use crate::from_bytes;
+
+    // 2019-05-23T12:30:15Z
+    const EXPECTED_SECS: u64 = 1_558_614_615;
+
+    fn deserialize(input: Term) -> SystemTime {
+        let mut cursor = io::Cursor::new(vec![]);
+        input.encode(&mut cursor).expect("encode failed");
+
+        let time: FlexibleTime = from_bytes(&cursor.into_inner()).expect("deserialize failed");
+        time.into()
+    }
+
+    fn int(value: i32) -> Term {
+        Term::FixInteger(eetf::FixInteger::from(value))
+    }
+
+    fn tuple(elements: Vec<Term>) -> Term {
+        Term::Tuple(eetf::Tuple::from(elements))
+    }
+
+    #[test]
+    fn test_unix_millis() {
+        let result = deserialize(Term::BigInteger(eetf::BigInteger::from(
+            EXPECTED_SECS * 1000,
+        )));
+
+        assert_eq!(result, UNIX_EPOCH + Duration::from_secs(EXPECTED_SECS));
+    }
+
+    #[test]
+    fn test_erlang_timestamp() {
+        let result = deserialize(tuple(vec![int(1558), int(614_615), int(0)]));
+
+        assert_eq!(result, UNIX_EPOCH + Duration::from_secs(EXPECTED_SECS));
+    }
+
+    #[test]
+    fn test_calendar_datetime() {
+        let result = deserialize(tuple(vec![
+            tuple(vec![int(2019), int(5), int(23)]),
+            tuple(vec![int(12), int(30), int(15)]),
+        ]));
+
+        assert_eq!(result, UNIX_EPOCH + Duration::from_secs(EXPECTED_SECS));
+    }
+
+    #[test]
+    fn test_days_from_civil() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(days_from_civil(1969, 12, 31), -1);
+        assert_eq!(days_from_civil(2000, 3, 1), 11_017);
+    }
+}
@@ -0,0 +1,281 @@
+//! A dynamically typed representation of any EETF term.
+
+use std::cell::RefCell;
+use std::fmt;
+use std::io::{self, Read};
+
+use eetf::Term;
+use serde::de::{self, Deserialize, Deserializer, Visitor};
+use serde::ser::{self, Serialize, Serializer};
+
+use crate::error::Result;
+
+// The name of the newtype struct that EetfValue (de)serializes as.  Our
+// Serializer & Deserializer recognise this, and hand the term over through
+// PASSED_TERM rather than serde's data model, which has no way to hold it.
+// Other formats see the term encoded as bytes.
+pub(crate) const TOKEN: &str = "$serde_eetf::EetfValue";
+
+thread_local! {
+    // A term being handed between an EetfValue & our Serializer or
+    // Deserializer, while the TOKEN newtype struct is being processed.
+    static PASSED_TERM: RefCell<Option<Term>> = RefCell::new(None);
+}
+
+// Leaves `term` for the other side of a TOKEN newtype struct to take.
+pub(crate) fn pass_term(term: Term) {
+    PASSED_TERM.with(|passed| *passed.borrow_mut() = Some(term));
+}
+
+// Takes the term left by `pass_term`, if there is one.
+pub(crate) fn take_term() -> Option<Term> {
+    PASSED_TERM.with(|passed| passed.borrow_mut().take())
+}
+
+/// Any EETF term.
+///
+/// Useful for inspecting a message before deciding which rust type to
+/// deserialize it into.  This is a thin wrapper around `eetf::Term`, so match
+/// on the inner term to see what was received.
+///
+/// It can also be used as a field of a type being serialized or deserialized,
+/// to pass part of a message through as is.
+///
+/// Terms like pids have no equivalent in the serde data model, so this and
+/// the types built on it (`Pid`, `Reference`, `Port`, `ImproperList` &
+/// `BitBinary`) only work with this crate's serializer & deserializer.  Any
+/// other serde format sees an opaque newtype struct holding bytes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EetfValue(pub Term);
+
+impl From<Term> for EetfValue {
+    fn from(term: Term) -> Self {
+        EetfValue(term)
+    }
+}
+
+impl From<EetfValue> for Term {
+    fn from(value: EetfValue) -> Self {
+        value.0
+    }
+}
+
+impl fmt::Display for EetfValue {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(formatter)
+    }
+}
+
+impl Serialize for EetfValue {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        pass_term(self.0.clone());
+        serializer.serialize_newtype_struct(TOKEN, &EncodedTerm(&self.0))
+    }
+}
+
+// A term that's serialized as its encoded bytes, for formats other than
+// ours.  Our Serializer takes the passed term instead, and never gets here.
+struct EncodedTerm<'a>(&'a Term);
+
+impl<'a> Serialize for EncodedTerm<'a> {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        take_term();
+        let mut bytes = Vec::new();
+        self.0.encode(&mut bytes).map_err(ser::Error::custom)?;
+        serializer.serialize_bytes(&bytes)
+    }
+}
+
+impl<'de> Deserialize<'de> for EetfValue {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_newtype_struct(TOKEN, EetfValueVisitor)
+    }
+}
+
+struct EetfValueVisitor;
+
+impl<'de> Visitor<'de> for EetfValueVisitor {
+    type Value = EetfValue;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an EETF term, from the serde_eetf deserializer")
+    }
+
+    // Our Deserializer passes the term, then visits a unit.
+    fn visit_unit<E>(self) -> std::result::Result<EetfValue, E>
+    where
+        E: de::Error,
+    {
+        match take_term() {
+            Some(term) => Ok(EetfValue(term)),
+            None => Err(de::Error::invalid_type(de::Unexpected::Unit, &self)),
+        }
+    }
+
+    // Other formats give us the encoded term, borrowed or not.
+    fn visit_bytes<E>(self, v: &[u8]) -> std::result::Result<EetfValue, E>
+    where
+        E: de::Error,
+    {
+        value_from_bytes(v).map_err(de::Error::custom)
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> std::result::Result<EetfValue, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_bytes(self)
+    }
+}
+
+// Serializes a term as an EetfValue, for types wrapping a kind of term that
+// has no equivalent in the serde data model.
+pub(crate) fn serialize_term<S>(term: Term, serializer: S) -> std::result::Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    EetfValue(term).serialize(serializer)
+}
+
+// Deserializes a term as an EetfValue & converts it with `convert`, which
+// hands back any term of the wrong kind.  `expected` describes the right
+// kind, e.g. "a pid".
+pub(crate) fn deserialize_term<'de, D, T, F>(
+    deserializer: D,
+    expected: &str,
+    convert: F,
+) -> std::result::Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    F: FnOnce(Term) -> std::result::Result<T, Term>,
+{
+    let EetfValue(term) = EetfValue::deserialize(deserializer)?;
+    convert(term)
+        .map_err(|other| de::Error::custom(format!("expected {}, got {}", expected, other)))
+}
+
+/// Decodes any EETF term from a Read, without needing to know its type.
+pub fn value_from_reader<R>(reader: R) -> Result<EetfValue>
+where
+    R: Read,
+{
+    Ok(EetfValue(Term::decode(reader)?))
+}
+
+/// Decodes any EETF term from a slice of bytes, without needing to know its
+/// type.
+pub fn value_from_bytes(bytes: &[u8]) -> Result<EetfValue> {
+    value_from_reader(io::Cursor::new(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_value_from_bytes() {
+        let pid = Term::Pid(eetf::Pid {
+            node: eetf::Atom::from("node@localhost"),
+            id: 42,
+            serial: 0,
+            creation: 1,
+        });
+        let reference = Term::Reference(eetf::Reference {
+            node: eetf::Atom::from("node@localhost"),
+            id: vec![1, 2, 3],
+            creation: 1,
+        });
+        let message = Term::Tuple(eetf::Tuple::from(vec![
+            Term::Atom(eetf::Atom::from("reply")),
+            pid.clone(),
+            reference.clone(),
+            Term::Binary(eetf::Binary::from("ok".as_bytes())),
+            Term::List(eetf::List::from(vec![
+                Term::FixInteger(eetf::FixInteger::from(1)),
+                Term::Float(eetf::Float { value: 2.5 }),
+            ])),
+        ]));
+        let mut cursor = io::Cursor::new(vec![]);
+        message.encode(&mut cursor).expect("encode failed");
+
+        let result = value_from_bytes(&cursor.into_inner()).expect("decode failed");
+
+        match result {
+            EetfValue(Term::Tuple(ref tuple)) => {
+                assert_eq!(tuple.elements[1], pid);
+                assert_eq!(tuple.elements[2], reference);
+            }
+            ref other => panic!("Expected a tuple, got {}", other),
+        }
+        assert_eq!(result, EetfValue(message));
+    }
+
+    #[test]
+    fn test_value_roundtrip_in_struct() {
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Envelope {
+            id: u8,
+            payload: EetfValue,
+        }
+
+        let envelope = Envelope {
+            id: 1,
+            payload: EetfValue(Term::Tuple(eetf::Tuple::from(vec![
+                Term::Atom(eetf::Atom::from("pong")),
+                Term::Pid(eetf::Pid {
+                    node: eetf::Atom::from("node@localhost"),
+                    id: 42,
+                    serial: 0,
+                    creation: 1,
+                }),
+            ]))),
+        };
+
+        let bytes = crate::to_bytes(&envelope).expect("serialize failed");
+        match value_from_bytes(&bytes).expect("decode failed") {
+            EetfValue(Term::Map(map)) => assert!(map.entries.contains(&(
+                Term::Atom(eetf::Atom::from("payload")),
+                envelope.payload.0.clone()
+            ))),
+            other => panic!("Expected a map, got {}", other),
+        }
+
+        let result: Envelope = crate::from_bytes(&bytes).expect("deserialize failed");
+        assert_eq!(result, envelope);
+    }
+
+    #[test]
+    fn test_value_from_other_formats() {
+        let term = Term::Tuple(eetf::Tuple::from(vec![
+            Term::Atom(eetf::Atom::from("ok")),
+            Term::FixInteger(eetf::FixInteger::from(1)),
+        ]));
+        let mut bytes = vec![];
+        term.encode(&mut bytes).expect("encode failed");
+
+        let result = EetfValueVisitor.visit_bytes::<de::value::Error>(&bytes);
+        assert_eq!(result, Ok(EetfValue(term.clone())));
+        let result = EetfValueVisitor.visit_byte_buf::<de::value::Error>(bytes);
+        assert_eq!(result, Ok(EetfValue(term)));
+
+        // A unit is only accepted when our deserializer passed a term.
+        assert!(EetfValueVisitor.visit_unit::<de::value::Error>().is_err());
+    }
+
+    #[test]
+    fn test_value_from_bad_bytes() {
+        match value_from_bytes(&[131, 255]) {
+            Err(crate::Error::DecodeError(_)) => (),
+            other => panic!("Expected a decode error, got {:?}", other),
+        }
+    }
+}